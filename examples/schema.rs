@@ -3,8 +3,11 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use secret_batlesheep::msg::{HandleMsg, InitMsg, QueryMsg, Shots};
-use secret_batlesheep::state::Coords;
+use secret_batlesheep::archive::ArchivedGame;
+use secret_batlesheep::msg::{HandleMsg, InitMsg, MyPastureResponse, QueryMsg, Shots};
+use secret_batlesheep::presets::Preset;
+use secret_batlesheep::records::HeadToHead;
+use secret_batlesheep::state::{Coords, PlacementViolation, Template};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -16,5 +19,11 @@ fn main() {
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(Shots), &out_dir);
+    export_schema(&schema_for!(MyPastureResponse), &out_dir);
     export_schema(&schema_for!(Coords), &out_dir);
+    export_schema(&schema_for!(Preset), &out_dir);
+    export_schema(&schema_for!(Template), &out_dir);
+    export_schema(&schema_for!(PlacementViolation), &out_dir);
+    export_schema(&schema_for!(HeadToHead), &out_dir);
+    export_schema(&schema_for!(ArchivedGame), &out_dir);
 }
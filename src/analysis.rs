@@ -0,0 +1,164 @@
+//! Pure helpers for reasoning about a pasture from the shots fired at it.
+//!
+//! Bots can use these to pick shots, and the `Analysis` query uses them to review
+//! archived games. Nothing here is needed to play a game, so the module is only built
+//! with the `analysis` feature.
+
+use crate::presets::FleetEntry;
+use crate::state::{Coords, Pasture};
+
+/// What a shooter knows about a pasture from the shots they fired at it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Constraints {
+    /// Cells that were shot
+    pub shot: Vec<Coords>,
+    /// Cells no remaining herd can cover: missed shots, and the sheep of sunk herds
+    pub blocked: Vec<Coords>,
+    /// The herds that haven't been sunk yet, counted by length
+    pub fleet: Vec<FleetEntry>,
+}
+
+impl Constraints {
+    /// What the shooter knew about this pasture after the first `shots` shots at it
+    pub fn after_shots(pasture: &Pasture, shots: usize) -> Self {
+        let fired = &pasture.shots()[..shots.min(pasture.shots().len())];
+        let seen = Pasture::new(pasture.herds().to_vec(), fired.to_vec());
+
+        let mut blocked: Vec<Coords> = fired
+            .iter()
+            .copied()
+            .filter(|shot| !seen.is_hit(*shot))
+            .collect();
+        blocked.extend(seen.sunk_sheep());
+
+        Self {
+            shot: fired.to_vec(),
+            blocked,
+            fleet: seen.remaining_fleet(),
+        }
+    }
+
+    /// The amount of shots these constraints were learned from
+    pub fn shots(&self) -> u32 {
+        self.shot.len() as u32
+    }
+}
+
+/// Count, for every cell of a pasture of this size, the placements of remaining herds
+/// that are consistent with what is known and cover the cell.
+///
+/// Herds are counted one at a time, without checking that the remaining herds fit around
+/// them, which is the usual approximation of how likely each cell is to hold a sheep.
+/// Cells that were already shot count no placements. The counts are indexed by row first.
+pub fn remaining_placements(size: u8, constraints: &Constraints) -> Vec<Vec<u32>> {
+    let mut counts = vec![vec![0_u32; usize::from(size)]; usize::from(size)];
+    for entry in constraints.fleet.iter() {
+        for placement in placements(size, entry.length, &constraints.blocked) {
+            for (x, y) in placement {
+                counts[usize::from(y)][usize::from(x)] += entry.count;
+            }
+        }
+    }
+    for row in 0..size {
+        for column in 0..size {
+            if constraints.shot.contains(&Coords::new(column, row)) {
+                counts[usize::from(row)][usize::from(column)] = 0;
+            }
+        }
+    }
+
+    counts
+}
+
+/// Every placement of a herd of this length that lies within the pasture and covers no
+/// blocked cell, as the cells it covers
+fn placements(size: u8, length: u8, blocked: &[Coords]) -> Vec<Vec<(u8, u8)>> {
+    let mut found = vec![];
+    if length == 0 || length > size {
+        return found;
+    }
+
+    for line in 0..size {
+        for start in 0..=size - length {
+            let cells = start..start + length;
+            let mut candidates = vec![cells.clone().map(|x| (x, line)).collect::<Vec<_>>()];
+            // A single sheep covers the same cell in either orientation
+            if length > 1 {
+                candidates.push(cells.map(|y| (line, y)).collect());
+            }
+            for placement in candidates {
+                if placement
+                    .iter()
+                    .all(|&(x, y)| !blocked.contains(&Coords::new(x, y)))
+                {
+                    found.push(placement);
+                }
+            }
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Herd, Orientation};
+
+    #[test]
+    fn test_remaining_placements() {
+        let constraints = Constraints {
+            shot: vec![],
+            blocked: vec![],
+            fleet: vec![FleetEntry {
+                length: 2,
+                count: 1,
+            }],
+        };
+        assert_eq!(
+            remaining_placements(3, &constraints),
+            vec![vec![2, 3, 2], vec![3, 4, 3], vec![2, 3, 2]]
+        );
+
+        // A miss in the centre leaves only the placements along the edges
+        let constraints = Constraints {
+            shot: vec![Coords::new(1, 1)],
+            blocked: vec![Coords::new(1, 1)],
+            ..constraints
+        };
+        assert_eq!(
+            remaining_placements(3, &constraints),
+            vec![vec![2, 2, 2], vec![2, 0, 2], vec![2, 2, 2]]
+        );
+    }
+
+    #[test]
+    fn test_constraints_after_shots() {
+        let pasture = Pasture::new(
+            vec![
+                Herd::new(0, 0, 2, Orientation::Horizontal),
+                Herd::new(0, 2, 3, Orientation::Horizontal),
+            ],
+            vec![Coords::new(0, 0), Coords::new(2, 0), Coords::new(1, 0)],
+        );
+
+        let constraints = Constraints::after_shots(&pasture, 2);
+        assert_eq!(constraints.shot, vec![Coords::new(0, 0), Coords::new(2, 0)]);
+        assert_eq!(constraints.blocked, vec![Coords::new(2, 0)]);
+        assert_eq!(constraints.fleet.len(), 2);
+
+        // The last shot sinks the first herd, whose sheep no other herd can share
+        let constraints = Constraints::after_shots(&pasture, 10);
+        assert_eq!(
+            constraints.blocked,
+            vec![Coords::new(2, 0), Coords::new(0, 0), Coords::new(1, 0)]
+        );
+        assert_eq!(
+            constraints.fleet,
+            vec![FleetEntry {
+                length: 3,
+                count: 1
+            }]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{to_vec, Binary, HumanAddr, StdResult, Storage};
+
+use crate::presets::BoardConfig;
+use crate::state::{FullGame, Pasture, Player};
+use crate::storage::{self, paginate_bucket, Pagination};
+
+/// A public copy of a finished game, with both pastures revealed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ArchivedGame {
+    pub name: String,
+    pub board: BoardConfig,
+    /// The number of turns the game took
+    pub turns: u32,
+    /// The block height the game ended at
+    pub finished_at: u64,
+    pub winner: ArchivedPlayer,
+    pub loser: ArchivedPlayer,
+}
+
+impl ArchivedGame {
+    /// Snapshot a finished game, or `None` if the game isn't over
+    pub fn new(game: &FullGame, finished_at: u64) -> Option<Self> {
+        Some(Self {
+            name: game.name().to_string(),
+            board: game.board().clone(),
+            turns: game.turns(),
+            finished_at,
+            winner: ArchivedPlayer::new(game.winner()?),
+            loser: ArchivedPlayer::new(game.loser()?),
+        })
+    }
+
+    /// The digest of this game's transcript: the SHA-256 hash of the game serialized as
+    /// JSON, exactly as the `Archive` and `Transcript` queries return it
+    pub fn transcript_digest(&self) -> StdResult<Binary> {
+        let mut hasher = Sha256::new();
+        hasher.update(to_vec(self)?);
+        Ok(Binary(hasher.finalize().to_vec()))
+    }
+}
+
+/// A player of an archived game, without their password
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ArchivedPlayer {
+    pub username: String,
+    pub address: HumanAddr,
+    /// The player's herds, and the shots fired at them
+    pub pasture: Pasture,
+}
+
+impl ArchivedPlayer {
+    fn new(player: &Player) -> Self {
+        Self {
+            username: player.username().to_string(),
+            address: player.address().clone(),
+            pasture: player.pasture().clone(),
+        }
+    }
+}
+
+/// Add a finished game to the end of the archive
+pub fn archive_game<S: Storage>(storage: &mut S, game: &ArchivedGame) -> StdResult<()> {
+    let size = archive_size(storage)?;
+    storage::archive(storage).save(&size.to_be_bytes(), game)?;
+    storage::archive_index(storage).save(game.name.as_bytes(), &size)?;
+    storage::archive_size(storage).save(&(size + 1))
+}
+
+/// Load a page of the archive
+pub fn archived_games<S: Storage>(storage: &S, page: Pagination) -> StdResult<Vec<ArchivedGame>> {
+    let size = archive_size(storage)?;
+    paginate_bucket(&storage::archive_read(storage), size, page)
+}
+
+/// Load the archived game at this index
+pub fn archived_game<S: Storage>(storage: &S, index: u32) -> StdResult<ArchivedGame> {
+    storage::archive_read(storage).load(&index.to_be_bytes())
+}
+
+/// The index of the latest archived game with this name, and the game itself
+pub fn archived_game_named<S: Storage>(
+    storage: &S,
+    name: &str,
+) -> StdResult<Option<(u32, ArchivedGame)>> {
+    match storage::archive_index_read(storage).may_load(name.as_bytes())? {
+        Some(index) => Ok(Some((index, archived_game(storage, index)?))),
+        None => Ok(None),
+    }
+}
+
+fn archive_size<S: Storage>(storage: &S) -> StdResult<u32> {
+    storage::archive_size_read(storage)
+        .may_load()
+        .map(Option::unwrap_or_default)
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::Serialize;
+
+use cosmwasm_std::{StdError, StdResult};
+
+use crate::presets::MAX_PASTURE_SIZE;
+use crate::state::Coords;
+
+/// Define a string type that can't be longer than a fixed amount of bytes.
+///
+/// The length is checked while deserializing, so oversized values are rejected
+/// before they are hashed, compared or used as storage keys.
+macro_rules! bounded_string {
+    ($(#[$meta:meta])* $name:ident, $max:expr) => {
+        $(#[$meta])*
+        #[derive(Serialize, Clone, Default, PartialEq, Eq, Hash, JsonSchema)]
+        pub struct $name(String);
+
+        impl $name {
+            /// The largest amount of bytes a value may have
+            pub const MAX_LEN: usize = $max;
+
+            pub fn new(value: String) -> StdResult<Self> {
+                if value.len() > Self::MAX_LEN {
+                    return Err(StdError::generic_err(too_long(
+                        stringify!($name),
+                        Self::MAX_LEN,
+                    )));
+                }
+                Ok(Self(value))
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let value = String::deserialize(deserializer)?;
+                if value.len() > Self::MAX_LEN {
+                    return Err(<D::Error as de::Error>::custom(too_long(
+                        stringify!($name),
+                        Self::MAX_LEN,
+                    )));
+                }
+                Ok(Self(value))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = StdError;
+
+            fn from_str(value: &str) -> StdResult<Self> {
+                Self::new(value.to_string())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+fn too_long(name: &str, max_len: usize) -> String {
+    format!("{} can't be longer than {} bytes", name, max_len)
+}
+
+bounded_string!(
+    /// The name a player goes by in a game
+    Username,
+    64
+);
+bounded_string!(
+    /// A short public remark a player attaches to their shot
+    Comment,
+    140
+);
+bounded_string!(
+    /// The secret a player authenticates with
+    Password,
+    128
+);
+bounded_string!(
+    /// The name of a game, which is also the key it is stored under
+    GameName,
+    64
+);
+bounded_string!(
+    /// The name of a template, which is also the key it is stored under
+    TemplateName,
+    64
+);
+
+/// Coordinates sent by a player, which can't lie outside the largest pasture.
+///
+/// They still have to be checked against the board of their game, with `within`.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct BoundedCoords(Coords);
+
+impl BoundedCoords {
+    /// The coordinates, if they lie within a pasture of this size
+    pub fn within(self, size: u8) -> StdResult<Coords> {
+        if !self.0.is_within(size) {
+            return Err(StdError::generic_err(format!(
+                "{} lies outside the pasture",
+                self.0
+            )));
+        }
+        Ok(self.0)
+    }
+}
+
+impl From<Coords> for BoundedCoords {
+    fn from(coords: Coords) -> Self {
+        Self(coords)
+    }
+}
+
+// The bound is part of the schema, so clients can check coordinates before sending them
+impl JsonSchema for BoundedCoords {
+    fn schema_name() -> String {
+        "BoundedCoords".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut coordinate = u8::json_schema(gen).into_object();
+        coordinate.number().maximum = Some(f64::from(MAX_PASTURE_SIZE - 1));
+        let coordinate = Schema::Object(coordinate);
+
+        let mut schema = SchemaObject {
+            instance_type: Some(InstanceType::Object.into()),
+            ..SchemaObject::default()
+        };
+        schema.metadata().description = Some(
+            "Coordinates sent by a player, which can't lie outside the largest pasture".to_string(),
+        );
+        let object = schema.object();
+        for axis in &["x", "y"] {
+            object.required.insert(axis.to_string());
+            object
+                .properties
+                .insert(axis.to_string(), coordinate.clone());
+        }
+
+        schema.into()
+    }
+}
+
+impl<'de> Deserialize<'de> for BoundedCoords {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let coords = Coords::deserialize(deserializer)?;
+        if !coords.is_within(MAX_PASTURE_SIZE) {
+            return Err(<D::Error as de::Error>::custom(format!(
+                "{} lies outside the largest pasture",
+                coords
+            )));
+        }
+        Ok(Self(coords))
+    }
+}
@@ -3,186 +3,3930 @@
 #![allow(clippy::needless_pass_by_value)]
 
 use cosmwasm_std::{
-    to_binary, Api, Binary, Env, Extern, HandleResponse, InitResponse, Querier, StdError,
+    log, to_binary, to_vec, Api, BankMsg, Binary, BlockInfo, Coin, ContractInfo, CosmosMsg, Env,
+    Extern, HandleResponse, HumanAddr, InitResponse, LogAttribute, MessageInfo, Querier, StdError,
     StdResult, Storage,
 };
 
-use crate::msg::{Credentials, HandleMsg, InitMsg, QueryMsg};
-use crate::state::{Coords, Game, Pasture, Player};
+#[cfg(feature = "analysis")]
+use crate::analysis::{remaining_placements, Constraints};
+#[cfg(feature = "analysis")]
+use crate::archive::archived_game;
+use crate::archive::{archive_game, archived_game_named, archived_games, ArchivedGame};
+use crate::bounded::{BoundedCoords, Comment, GameName, Password, TemplateName};
+use crate::lobby::{list_game, list_page, listed_game, unlist_game};
+#[cfg(feature = "analysis")]
+use crate::msg::{AnalysisResponse, PastureAnalysis};
+use crate::msg::{
+    Callback, CallbackMsg, Credentials, Emote, GameListing, GameStatus, HandleMsg, InitMsg,
+    ListGamesResponse, LobbyStatus, MailboxMsg, ObservedSide, ObserverView, QueryMsg, ShotInfo,
+    SideStatus, SimulateResponse, SponsorsResponse, TemplatesResponse, TranscriptResponse,
+    TurnInfo, TurnSummary, VersionResponse,
+};
+use crate::practice::{tutor, tutor_shot};
+use crate::presets::{fleet_for_board, preset, presets, BoardConfig, DEFAULT_PRESET};
+use crate::records::HeadToHead;
+use crate::rules::{GameRules, VictoryCondition, RULES_VERSION};
+use crate::state::{
+    listed_template, template_page, ActionKind, Config, Coords, Deposit, FullGame, Game, Herd,
+    Pasture, Phase, Player, Role, Template, DEFAULT_ACTION_COOLDOWN, DEFAULT_GAME_LIFETIME,
+    STATE_VERSION,
+};
+use crate::storage::{write_batch, Pagination, ScratchStorage};
+use crate::treasury::{Sponsorship, Treasury};
+use std::ops::{Deref, DerefMut};
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
-    _deps: &mut Extern<S, A, Q>,
+    deps: &mut Extern<S, A, Q>,
     _env: Env,
-    _msg: InitMsg,
+    msg: InitMsg,
 ) -> StdResult<InitResponse> {
+    let config = Config {
+        game_lifetime: msg.game_lifetime.unwrap_or(DEFAULT_GAME_LIFETIME),
+        action_cooldown: msg.action_cooldown.unwrap_or(DEFAULT_ACTION_COOLDOWN),
+        mailbox: msg.mailbox,
+        lobby_deposit: msg.lobby_deposit,
+        banned_words: msg
+            .banned_words
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect(),
+        allowed_callbacks: msg.allowed_callbacks,
+        deprecations: msg.deprecations,
+    };
+    if config.game_lifetime == 0 {
+        return Err(StdError::generic_err(
+            "Games must last at least one block".to_string(),
+        ));
+    }
+    if config.banned_words.iter().any(String::is_empty) {
+        return Err(StdError::generic_err(
+            "Banned words can't be empty".to_string(),
+        ));
+    }
+    config.save(&mut deps.storage)?;
+
     Ok(InitResponse::default())
 }
 
 pub fn handle<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    _env: Env,
+    env: Env,
+    msg: HandleMsg,
+) -> StdResult<HandleResponse> {
+    write_batch(&mut deps.storage, |storage| execute(storage, env, msg))
+}
+
+/// Handle a message, with nothing but the storage it acts on.
+///
+/// The contract settings are loaded once here, and handed down to the handlers. Deprecated
+/// messages are refused once their sunset height is reached, and until then their responses
+/// name the replacement.
+fn execute<S: Storage>(storage: &mut S, env: Env, msg: HandleMsg) -> StdResult<HandleResponse> {
+    let config = Config::load(storage)?;
+    let deprecation = config
+        .check_deprecation(msg.name(), env.block.height)?
+        .cloned();
+    // Funds sent with any other message would be stuck in the contract
+    if !env.message.sent_funds.is_empty() && !msg.takes_funds() {
+        return Err(StdError::generic_err(format!(
+            "The {} message doesn't take any funds",
+            msg.name()
+        )));
+    }
+
+    let mut response = dispatch(storage, env, msg, &config)?;
+    if let Some(deprecation) = deprecation {
+        response
+            .log
+            .push(log("deprecated", deprecation.replacement));
+        response.log.push(log("sunset", deprecation.sunset));
+    }
+
+    Ok(response)
+}
+
+fn dispatch<S: Storage>(
+    storage: &mut S,
+    env: Env,
     msg: HandleMsg,
+    config: &Config,
 ) -> StdResult<HandleResponse> {
     match msg {
-        HandleMsg::NewGame { name } => try_new_game(&mut deps.storage, name),
+        HandleMsg::NewGame {
+            name,
+            preset,
+            size,
+            rules,
+            callback,
+            lifetime,
+            locale,
+            wager,
+        } => try_new_game(
+            storage, &env, config, name, preset, size, rules, callback, lifetime, locale, wager,
+        ),
+        HandleMsg::SaveTemplate {
+            name,
+            board,
+            rules,
+            lifetime,
+        } => try_save_template(
+            storage,
+            &env,
+            config,
+            Template {
+                name: name.into(),
+                organizer: env.message.sender.clone(),
+                board,
+                rules,
+                lifetime,
+            },
+        ),
+        HandleMsg::DeleteTemplate { name } => try_delete_template(storage, &env, name),
+        HandleMsg::NewGameFromTemplate { template, name } => {
+            try_new_game_from_template(storage, &env, config, template, name)
+        }
+        HandleMsg::CancelGame { name } => try_cancel_game(storage, &env, name),
+        HandleMsg::CollectDeposit { name } => try_collect_deposit(storage, &env, name),
+        HandleMsg::RefundStakes { name } => try_refund_stakes(storage, &env, name),
+        HandleMsg::NewPracticeGame {
+            pasture,
+            credentials,
+        } => try_new_practice_game(storage, &env, config, credentials, pasture),
         HandleMsg::Join {
             pasture,
             credentials,
-        } => try_join(&mut deps.storage, credentials, pasture),
+        } => try_join(storage, &env, config, credentials, pasture),
         HandleMsg::Shoot {
             coords,
+            comment,
             credentials,
-        } => try_shoot(&mut deps.storage, credentials, coords),
+        } => try_shoot(storage, &env, config, credentials, coords, comment),
         HandleMsg::Confirm {
             coords,
             credentials,
-        } => try_confirm(&mut deps.storage, credentials, coords),
+        } => try_confirm(storage, &env, config, credentials, coords),
+        HandleMsg::ConfirmBlind { credentials } => {
+            try_confirm_blind(storage, &env, config, credentials)
+        }
+        HandleMsg::Pass { credentials } => try_pass(storage, &env, config, credentials),
+        HandleMsg::RequestTakeback { credentials } => {
+            try_request_takeback(storage, &env, config, credentials)
+        }
+        HandleMsg::GrantTakeback { credentials } => {
+            try_grant_takeback(storage, &env, config, credentials)
+        }
+        HandleMsg::Emote { emote, credentials } => {
+            try_emote(storage, &env, config, credentials, emote)
+        }
+        HandleMsg::AllowObserver { key, credentials } => {
+            try_set_observer_key(storage, &env, config, credentials, Some(key))
+        }
+        HandleMsg::RevokeObserver { credentials } => {
+            try_set_observer_key(storage, &env, config, credentials, None)
+        }
+        HandleMsg::CommitOpening { hash, credentials } => {
+            try_commit_opening(storage, &env, config, credentials, hash)
+        }
+        HandleMsg::RevealOpening {
+            coords,
+            salt,
+            credentials,
+        } => try_reveal_opening(storage, &env, config, credentials, coords, salt),
+        HandleMsg::CommitShot { hash, credentials } => {
+            try_commit_shot(storage, &env, config, credentials, hash)
+        }
+        HandleMsg::RevealShot {
+            coords,
+            salt,
+            comment,
+            credentials,
+        } => try_reveal_shot(storage, &env, config, credentials, coords, salt, comment),
+        HandleMsg::PlaceHerd { herd, credentials } => {
+            try_place_herd(storage, &env, config, credentials, herd)
+        }
+        HandleMsg::Donate {} => try_donate(storage, &env),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn try_new_game<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    name: GameName,
+    preset_name: Option<String>,
+    size: Option<u8>,
+    rules: GameRules,
+    callback: Option<Callback>,
+    lifetime: Option<u64>,
+    locale: Option<String>,
+    wager: Option<Coin>,
+) -> StdResult<HandleResponse> {
+    let board = match (preset_name, size) {
+        (Some(_), Some(_)) => {
+            return Err(StdError::generic_err(
+                "Pick either a preset or a pasture size".to_string(),
+            ))
+        }
+        (None, Some(size)) => {
+            let board = BoardConfig {
+                size,
+                fleet: fleet_for_board(size),
+            };
+            board.validate()?;
+            board
+        }
+        (preset_name, None) => preset(preset_name.as_deref().unwrap_or(DEFAULT_PRESET))?,
+    };
+
+    let mut game = create_game(storage, env, config, name, board, rules, callback, lifetime)?;
+    if let Some(locale) = locale {
+        check_locale(&locale)?;
+        game.set_locale(locale);
+    }
+    if let Some(wager) = wager {
+        if wager.amount.is_zero() {
+            return Err(StdError::generic_err(
+                "A wager has to stake something".to_string(),
+            ));
+        }
+        game.set_wager(wager);
+    }
+    game.save(storage)?;
+    list_game(storage, game.name())?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_new_game_from_template<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    template_name: TemplateName,
+    name: GameName,
+) -> StdResult<HandleResponse> {
+    let template = Template::load(storage, &template_name)?;
+
+    let game = create_game(
+        storage,
+        env,
+        config,
+        name,
+        template.board,
+        template.rules,
+        None,
+        template.lifetime,
+    )?;
+    game.save(storage)?;
+    list_game(storage, game.name())?;
+
+    Ok(HandleResponse::default())
+}
+
+/// Set up a new game, taking the lobby deposit if one is required.
+///
+/// The game still has to be saved.
+#[allow(clippy::too_many_arguments)]
+fn create_game<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    name: GameName,
+    board: BoardConfig,
+    rules: GameRules,
+    callback: Option<Callback>,
+    lifetime: Option<u64>,
+) -> StdResult<Game> {
+    check_name_free(storage, &name)?;
+    check_lifetime(lifetime)?;
+    rules.validate()?;
+    config.check_words(&name)?;
+    if let Some(callback) = &callback {
+        config.check_callback(callback)?;
+    }
+    let lifetime = lifetime.unwrap_or(config.game_lifetime);
+    let expires_at = env.block.height.saturating_add(lifetime);
+
+    let mut game = Game::new(name.into(), board, rules, callback, expires_at);
+    if let Some(amount) = &config.lobby_deposit {
+        if env.message.sent_funds != [amount.clone()] {
+            return Err(StdError::generic_err(format!(
+                "Opening a lobby requires a deposit of {}{}",
+                amount.amount, amount.denom
+            )));
+        }
+        game.hold_deposit(Deposit {
+            owner: env.message.sender.clone(),
+            amount: amount.clone(),
+        });
+    } else if !env.message.sent_funds.is_empty() {
+        return Err(StdError::generic_err(
+            "Opening a lobby takes no deposit".to_string(),
+        ));
+    }
+
+    Ok(game)
+}
+
+/// Cancel a lobby that hasn't started, and refund its deposit and stakes
+fn try_cancel_game<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    name: GameName,
+) -> StdResult<HandleResponse> {
+    let mut game = Game::load(storage, name.into())?;
+    // Expired lobbies forfeit their deposit instead
+    game.check_expiry(env.block.height)?;
+    if game.is_full() {
+        return Err(StdError::generic_err(
+            "The game already started".to_string(),
+        ));
+    }
+    // Lobbies opened without a deposit have no known organizer
+    let deposit = match game.deposit() {
+        Some(deposit) if deposit.owner == env.message.sender => deposit.clone(),
+        _ => return Err(StdError::unauthorized()),
+    };
+
+    game.remove(storage);
+    unlist_game(storage, game.name())?;
+
+    let mut messages = vec![refund(env, deposit)];
+    messages.extend(
+        game.take_stakes()
+            .into_iter()
+            .map(|stake| refund(env, stake)),
+    );
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Move the deposit of a lobby that expired before it started to the treasury
+fn try_collect_deposit<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    name: GameName,
+) -> StdResult<HandleResponse> {
+    let mut game = Game::load(storage, name.into())?;
+    if !game.is_expired(env.block.height) {
+        return Err(StdError::generic_err(
+            "The lobby hasn't expired yet".to_string(),
+        ));
+    }
+    let deposit = game
+        .take_deposit()
+        .ok_or_else(|| StdError::generic_err("The game holds no deposit to collect".to_string()))?;
+
+    let mut treasury = Treasury::load(storage)?;
+    treasury.collect(&deposit.amount);
+    treasury.save(storage)?;
+    game.save(storage)?;
+
+    Ok(HandleResponse::default())
+}
+
+/// Send the stakes of a wagered lobby that expired before it started back to their players
+fn try_refund_stakes<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    name: GameName,
+) -> StdResult<HandleResponse> {
+    let mut game = Game::load(storage, name.into())?;
+    if game.winner().is_some() {
+        return Err(StdError::generic_err(
+            "The game was already decided".to_string(),
+        ));
+    }
+    if !game.is_expired(env.block.height) {
+        return Err(StdError::generic_err(
+            "The game hasn't expired yet".to_string(),
+        ));
+    }
+    if game.wager().is_none() {
+        return Err(StdError::generic_err(
+            "The game holds no stakes to refund".to_string(),
+        ));
+    }
+    // Letting a started game run out is no way to get a losing stake back
+    if game.is_full() {
+        let mut full = game.clone().full()?;
+        if full.forfeit_stalled() {
+            return settle_forfeit(storage, env, full);
+        }
+    }
+    let stakes = game.take_stakes();
+    if stakes.is_empty() {
+        return Err(StdError::generic_err(
+            "The game holds no stakes to refund".to_string(),
+        ));
+    }
+    game.save(storage)?;
+
+    Ok(HandleResponse {
+        messages: stakes.into_iter().map(|stake| refund(env, stake)).collect(),
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Pay the pot of a wagered game that was just forfeited to its winner, and announce the result
+fn settle_forfeit<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    mut game: FullGame,
+) -> StdResult<HandleResponse> {
+    let mut messages: Vec<CosmosMsg> = pay_out(env, &mut game).into_iter().collect();
+    let log = game_over(storage, &game, env.block.height)?;
+    game.save(storage)?;
+
+    if let (Some(callback), Some(stats)) = (game.callback(), game.stats(env.block.height)) {
+        messages.push(callback.notify(&CallbackMsg::GameOver {
+            game: game.name().to_string(),
+            stats,
+        })?);
     }
+
+    Ok(HandleResponse {
+        messages,
+        log,
+        data: None,
+    })
+}
+
+/// Send the pot of a wagered game that was just decided to its winner
+fn pay_out(env: &Env, game: &mut Game) -> Option<CosmosMsg> {
+    let winner = game.winner()?.address().clone();
+    let pot = game.take_pot()?;
+    Some(CosmosMsg::Bank(BankMsg::Send {
+        from_address: env.contract.address.clone(),
+        to_address: winner,
+        amount: vec![pot],
+    }))
+}
+
+fn refund(env: &Env, deposit: Deposit) -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        from_address: env.contract.address.clone(),
+        to_address: deposit.owner,
+        amount: vec![deposit.amount],
+    })
 }
 
-fn try_new_game<S: Storage>(storage: &mut S, name: String) -> StdResult<HandleResponse> {
+fn check_name_free<S: Storage>(storage: &S, name: &str) -> StdResult<()> {
     // As long as the storage isn't corrupted somehow, this `?` should always succeed.
-    if Game::may_load(storage, name.clone())?.is_some() {
+    if Game::may_load(storage, name.to_string())?.is_some() {
         return Err(StdError::generic_err(format!(
             "game with name {:?} already exists",
             name
         )));
     }
 
-    Game::new(name).save(storage)?;
+    Ok(())
+}
+
+/// Games that can't last a single block could never be played
+fn check_lifetime(lifetime: Option<u64>) -> StdResult<()> {
+    if lifetime == Some(0) {
+        return Err(StdError::generic_err(
+            "Games must last at least one block".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// The language tags games can be tagged with
+pub const LOCALES: &[&str] = &[
+    "ar", "de", "en", "es", "fr", "hi", "it", "ja", "ko", "nl", "pl", "pt", "ru", "tr", "uk", "zh",
+];
+
+fn check_locale(locale: &str) -> StdResult<()> {
+    if !LOCALES.contains(&locale) {
+        return Err(StdError::generic_err(format!(
+            "Unknown locale {:?}, expected one of {}",
+            locale,
+            LOCALES.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+fn try_save_template<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    template: Template,
+) -> StdResult<HandleResponse> {
+    if let Some(existing) = Template::may_load(storage, &template.name)? {
+        if existing.organizer != env.message.sender {
+            return Err(StdError::unauthorized());
+        }
+    }
+    config.check_words(&template.name)?;
+    template.board.validate()?;
+    check_lifetime(template.lifetime)?;
+    template.rules.validate()?;
+
+    template.save(storage)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_delete_template<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    name: TemplateName,
+) -> StdResult<HandleResponse> {
+    let template = Template::load(storage, &name)?;
+    if template.organizer != env.message.sender {
+        return Err(StdError::unauthorized());
+    }
+
+    template.remove(storage)?;
 
     Ok(HandleResponse::default())
 }
 
+fn try_new_practice_game<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    pasture: Pasture,
+) -> StdResult<HandleResponse> {
+    check_name_free(storage, &credentials.game)?;
+    config.check_words(&credentials.game)?;
+    config.check_words(&credentials.username)?;
+    let lifetime = config.game_lifetime;
+    let expires_at = env.block.height.saturating_add(lifetime);
+
+    let mut game = Game::new_practice(credentials.game.into(), expires_at);
+    game.add_player(Player::new(
+        credentials.username,
+        credentials.password,
+        pasture,
+        env.message.sender.clone(),
+    ))?;
+    game.add_player(tutor()?)?;
+    game.start(env.block.height);
+    game.start_turn(env.block.height);
+
+    game.save(storage)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: turn_started_log(&game),
+        data: None,
+    })
+}
+
 fn try_join<S: Storage>(
     storage: &mut S,
+    env: &Env,
+    config: &Config,
     credentials: Credentials,
     pasture: Pasture,
 ) -> StdResult<HandleResponse> {
-    let mut game = Game::load(storage, credentials.game.clone())?;
-    let player = Player::new(credentials.username, credentials.password, pasture);
+    let mut game = Game::load(storage, credentials.game.clone().into())?;
+    game.check_expiry(env.block.height)?;
+    config.check_words(&credentials.username)?;
+    if game.is_full() {
+        return Err(StdError::generic_err(String::from("Game already full!")));
+    }
+    let stake = match game.wager() {
+        Some(wager) if env.message.sent_funds != [wager.clone()] => {
+            return Err(StdError::generic_err(format!(
+                "Joining this game requires a stake of {}{}",
+                wager.amount, wager.denom
+            )));
+        }
+        Some(wager) => Some(Deposit {
+            owner: env.message.sender.clone(),
+            amount: wager.clone(),
+        }),
+        None if !env.message.sent_funds.is_empty() => {
+            return Err(StdError::generic_err(
+                "This game isn't played for a wager".to_string(),
+            ));
+        }
+        None => None,
+    };
+    let player = Player::new(
+        credentials.username,
+        credentials.password,
+        pasture,
+        env.message.sender.clone(),
+    );
     game.add_player(player)?;
+    if let Some(stake) = stake {
+        game.hold_stake(stake);
+    }
+
+    let mut messages = vec![];
+    if game.is_full() {
+        game.start(env.block.height);
+        if let Some(deposit) = game.take_deposit() {
+            messages.push(refund(env, deposit));
+        }
+    }
+    let mut log = vec![];
+    if game.is_full() && !game.in_opening() {
+        game.start_turn(env.block.height);
+        messages.extend(notify_mailbox(config, &game)?);
+        log = turn_started_log(&game);
+    }
 
     game.save(storage)?;
 
-    Ok(HandleResponse::default())
+    Ok(HandleResponse {
+        messages,
+        log,
+        data: None,
+    })
 }
 
 fn try_shoot<S: Storage>(
     storage: &mut S,
+    env: &Env,
+    config: &Config,
     credentials: Credentials,
-    coords: Coords,
+    coords: BoundedCoords,
+    comment: Option<Comment>,
 ) -> StdResult<HandleResponse> {
-    let mut game = Game::load(storage, credentials.game.clone())?.full()?;
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
 
-    if game.player().matches_credentials(&credentials) {
-        return Err(StdError::generic_err("It's not your turn".to_string()));
-    }
+    authorize(&game, &credentials, ActionKind::Shoot)?;
+    let coords = coords.within(game.board().size)?;
+    game.rate_limit(env, &credentials)?;
+    game.comment(comment)?;
     game.shoot(coords);
 
+    let mut log = vec![];
+    if game.is_practice() {
+        log = tutor_reply(&mut game, coords, env.block.height)?;
+    }
+
     game.save(storage)?;
 
-    Ok(HandleResponse::default())
+    Ok(HandleResponse {
+        messages: vec![],
+        log,
+        data: None,
+    })
+}
+
+/// Have the tutor of a practice game confirm the player's shot, and fire back
+fn tutor_reply(game: &mut FullGame, coords: Coords, height: u64) -> StdResult<Vec<LogAttribute>> {
+    let hit = game.opponent().pasture().is_hit(coords);
+    game.confirm_shot(coords)?;
+    game.resolve_shot(hit, height);
+    if game.winner().is_some() {
+        let mut log = vec![log("hit", hit)];
+        log.extend(game_over_log(game, height)?);
+        return Ok(log);
+    }
+
+    let shot = tutor_shot(game.turns() / 2, game.board().size);
+    game.shoot(shot);
+
+    let mut log = vec![log("hit", hit), log("tutor_shot", shot)];
+    log.extend(turn_started_log(game));
+    Ok(log)
 }
 
 fn try_confirm<S: Storage>(
     storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    coords: BoundedCoords,
+) -> StdResult<HandleResponse> {
+    let game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::Confirm)?;
+    let coords = coords.within(game.board().size)?;
+
+    resolve_pending_shot(storage, env, game, &credentials, coords)
+}
+
+/// Confirm the pending shot of a game with strict confirms, which the player confirming it
+/// doesn't get to see
+fn try_confirm_blind<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
     credentials: Credentials,
+) -> StdResult<HandleResponse> {
+    let game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::ConfirmBlind)?;
+    let coords = game
+        .next_shot()
+        .ok_or_else(|| StdError::generic_err("There is no shot to confirm".to_string()))?;
+
+    resolve_pending_shot(storage, env, game, &credentials, coords)
+}
+
+/// Resolve the pending shot at these coordinates, once the player being shot at confirmed it
+fn resolve_pending_shot<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    mut game: GameCtx<'_>,
+    credentials: &Credentials,
     coords: Coords,
 ) -> StdResult<HandleResponse> {
-    let mut game = Game::load(storage, credentials.game.clone())?.full()?;
+    game.rate_limit(env, credentials)?;
+    let hit = game.opponent().pasture().is_hit(coords);
+    game.confirm_shot(coords)?;
+    let mut messages = vec![];
+    game.resolve_shot(hit, env.block.height);
+    let log = if game.winner().is_some() {
+        messages.extend(pay_out(env, &mut game));
+        game_over(storage, &game, env.block.height)?
+    } else {
+        messages.extend(notify_mailbox(game.config, &game)?);
+        turn_started_log(&game)
+    };
 
-    if game.opponent().matches_credentials(&credentials) {
-        return Err(StdError::generic_err(
-            "You do not have permissions to confirm this shot".to_string(),
-        ));
+    game.save(storage)?;
+
+    if let Some(callback) = game.callback() {
+        messages.push(callback.notify(&CallbackMsg::TurnResolved {
+            game: game.name().to_string(),
+            turn: game.turns(),
+            coords,
+            hit,
+        })?);
+        if let Some(stats) = game.stats(env.block.height) {
+            messages.push(callback.notify(&CallbackMsg::GameOver {
+                game: game.name().to_string(),
+                stats,
+            })?);
+        }
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log,
+        data: None,
+    })
+}
+
+fn try_pass<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::Pass)?;
+    game.rate_limit(env, &credentials)?;
+
+    let mut attributes = vec![log("passed", game.player().username())];
+    let mut messages = vec![];
+    game.pass(env.block.height);
+    if game.winner().is_some() {
+        messages.extend(pay_out(env, &mut game));
+        attributes.extend(game_over(storage, &game, env.block.height)?);
+        if let (Some(callback), Some(stats)) = (game.callback(), game.stats(env.block.height)) {
+            messages.push(callback.notify(&CallbackMsg::GameOver {
+                game: game.name().to_string(),
+                stats,
+            })?);
+        }
+    } else {
+        messages.extend(notify_mailbox(game.config, &game)?);
+        attributes.extend(turn_started_log(&game));
     }
-    game.confirm_shot(coords);
-    game.end_turn();
 
     game.save(storage)?;
 
-    Ok(HandleResponse::default())
+    Ok(HandleResponse {
+        messages,
+        log: attributes,
+        data: None,
+    })
 }
 
-pub fn query<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    msg: QueryMsg,
-) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::MyPasture { credentials } => try_get_my_pasture(&deps.storage, credentials),
-        QueryMsg::MyShots { credentials } => try_get_my_shots(&deps.storage, credentials),
-        QueryMsg::LastShot { credentials } => try_get_last_shot(&deps.storage, credentials),
+/// Record the result of a game that just ended, and announce it
+fn game_over<S: Storage>(
+    storage: &mut S,
+    game: &FullGame,
+    height: u64,
+) -> StdResult<Vec<LogAttribute>> {
+    let transcript = record_result(storage, game, height)?;
+    let mut attributes = game_over_log(game, height)?;
+    attributes.extend(transcript.map(|digest| log("transcript", digest.to_base64())));
+
+    Ok(attributes)
+}
+
+/// Announce the winner of a game that just ended, together with its statistics as JSON
+fn game_over_log(game: &Game, height: u64) -> StdResult<Vec<LogAttribute>> {
+    let mut attributes = vec![];
+    if let Some(winner) = game.winner() {
+        attributes.push(log("winner", winner.username()));
+    }
+    if let Some(stats) = game.stats(height) {
+        attributes.push(log("stats", String::from_utf8_lossy(&to_vec(&stats)?)));
     }
+
+    Ok(attributes)
 }
 
-fn try_get_my_pasture<S: Storage>(storage: &S, credentials: Credentials) -> StdResult<Binary> {
-    let game = Game::load(storage, credentials.game.clone())?.full()?;
+/// Keep the result of a finished game, in the public archive and in the head-to-head score
+/// of its players, and return the digest of its transcript. Practice games are left out of
+/// both, and have no transcript.
+fn record_result<S: Storage>(
+    storage: &mut S,
+    game: &FullGame,
+    height: u64,
+) -> StdResult<Option<Binary>> {
+    if game.is_practice() {
+        return Ok(None);
+    }
+    let archived = ArchivedGame::new(game, height)
+        .ok_or_else(|| StdError::generic_err("The game is not over yet".to_string()))?;
+    archive_game(storage, &archived)?;
+    let digest = archived.transcript_digest()?;
 
-    let pasture = game
-        .player()
-        .pasture(&credentials)
-        .or_else(|| game.opponent().pasture(&credentials))
-        .ok_or_else(|| {
-            StdError::generic_err("You do not have permissions to get the shots".to_string())
-        })?;
+    let (winner, loser) = (&archived.winner.address, &archived.loser.address);
+    // Games where a player has no known address, or played against themselves, don't count
+    if !winner.is_empty() && !loser.is_empty() && winner != loser {
+        HeadToHead::record_win(storage, winner, loser)?;
+    }
 
-    to_binary(pasture)
+    Ok(Some(digest))
 }
 
-pub fn try_get_my_shots<S: Storage>(storage: &S, credentials: Credentials) -> StdResult<Binary> {
-    let game = Game::load(storage, credentials.game.clone())?.full()?;
-    let player = game.player();
-    let opponent = game.opponent();
-    let shots = if player.matches_credentials(&credentials) {
-        game.get_player_shots()
-    } else if opponent.matches_credentials(&credentials) {
-        game.get_opponent_shots()
-    } else {
-        return Err(StdError::generic_err(
-            "You do not have permissions to get this information".to_string(),
-        ));
-    };
+fn try_request_takeback<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::RequestTakeback)?;
+    game.rate_limit(env, &credentials)?;
+    game.request_takeback();
 
-    to_binary(&shots)
+    game.save(storage)?;
+
+    Ok(HandleResponse::default())
 }
 
-pub fn try_get_last_shot<S: Storage>(storage: &S, credentials: Credentials) -> StdResult<Binary> {
-    let game = Game::load(storage, credentials.game.clone())?.full()?;
-    let player = game.player();
-    let opponent = game.opponent();
-    let last_shot =
-        if player.matches_credentials(&credentials) || opponent.matches_credentials(&credentials) {
-            game.next_shot()
-        } else {
-            return Err(StdError::generic_err(
-                "You do not have permissions to get this information".to_string(),
-            ));
-        };
+fn try_grant_takeback<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::GrantTakeback)?;
+    game.rate_limit(env, &credentials)?;
 
-    to_binary(&last_shot)
-}
+    game.grant_takeback();
 
-#[cfg(test)]
-mod tests {
-    use cosmwasm_std::testing::{mock_dependencies, mock_env};
-    use cosmwasm_std::{coins, from_binary, from_slice, to_vec, StdError};
+    game.save(storage)?;
 
-    use crate::state::{Herd, Orientation};
+    Ok(HandleResponse::default())
+}
 
-    use super::*;
+fn try_emote<S: Storage>(
+    storage: &S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    emote: Emote,
+) -> StdResult<HandleResponse> {
+    let game = GameCtx::load(storage, env, &credentials, config)?;
+    if game.role_of(&credentials)? != Role::Player || game.phase() == Phase::Finished {
+        return Err(StdError::generic_err(
+            "You can only emote during your own turn".to_string(),
+        ));
+    }
 
-    #[test]
-    fn test_herd_serialize() {
-        let serialized =
-            "{\"orientation\": \"horizontal\", \"length\": 3, \"coords\": {\"x\": 2, \"y\": 4}}"
-                .as_bytes();
-        let herd: Herd = from_slice(&serialized).unwrap();
-        println!("{:?}", herd);
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("emote", emote.code()),
+            log("player", game.player().username()),
+        ],
+        data: None,
+    })
+}
+
+fn try_set_observer_key<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    key: Option<Password>,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    game.role_of(&credentials)?;
+    game.rate_limit(env, &credentials)?;
+
+    game.set_observer_key(&credentials, key)?;
+
+    game.save(storage)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_commit_opening<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    hash: Binary,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::CommitOpening)?;
+    game.rate_limit(env, &credentials)?;
+
+    game.commit_opening(&credentials, hash)?;
+
+    game.save(storage)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_reveal_opening<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    coords: BoundedCoords,
+    salt: String,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::RevealOpening)?;
+    let coords = coords.within(game.board().size)?;
+    game.rate_limit(env, &credentials)?;
+
+    game.reveal_opening(&credentials, coords, &salt, env.block.height)?;
+
+    let mut messages = vec![];
+    let mut log = vec![];
+    if !game.in_opening() {
+        messages.extend(notify_mailbox(game.config, &game)?);
+        log = turn_started_log(&game);
+    }
+
+    game.save(storage)?;
+
+    Ok(HandleResponse {
+        messages,
+        log,
+        data: None,
+    })
+}
+
+fn try_commit_shot<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    hash: Binary,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::CommitShot)?;
+    game.rate_limit(env, &credentials)?;
+
+    game.commit_shot(hash, env.block.height);
+
+    game.save(storage)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_reveal_shot<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    coords: BoundedCoords,
+    salt: String,
+    comment: Option<Comment>,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::RevealShot)?;
+    let coords = coords.within(game.board().size)?;
+    game.rate_limit(env, &credentials)?;
+
+    game.reveal_shot(coords, &salt, env.block.height)?;
+    game.comment(comment)?;
+
+    game.save(storage)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_place_herd<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    credentials: Credentials,
+    herd: Herd,
+) -> StdResult<HandleResponse> {
+    let mut game = GameCtx::load(storage, env, &credentials, config)?;
+    authorize(&game, &credentials, ActionKind::PlaceHerd)?;
+    game.rate_limit(env, &credentials)?;
+
+    game.reinforce(herd)?;
+
+    game.save(storage)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn try_donate<S: Storage>(storage: &mut S, env: &Env) -> StdResult<HandleResponse> {
+    let amount: Vec<_> = env
+        .message
+        .sent_funds
+        .iter()
+        .filter(|coin| !coin.amount.is_zero())
+        .cloned()
+        .collect();
+    if amount.is_empty() {
+        return Err(StdError::generic_err(
+            "Send some funds to donate".to_string(),
+        ));
+    }
+
+    let mut treasury = Treasury::load(storage)?;
+    treasury.donate(
+        storage,
+        Sponsorship {
+            sponsor: env.message.sender.clone(),
+            amount,
+            height: env.block.height,
+        },
+    )?;
+    treasury.save(storage)?;
+
+    Ok(HandleResponse::default())
+}
+
+/// Tell the player whose turn it is that their turn started, if a mailbox is configured
+fn notify_mailbox(config: &Config, game: &Game) -> StdResult<Option<CosmosMsg>> {
+    let mailbox = match &config.mailbox {
+        Some(mailbox) if !game.is_practice() => mailbox,
+        _ => return Ok(None),
+    };
+
+    match game.shooter() {
+        Some(player) if !player.address().is_empty() => mailbox
+            .notify(&MailboxMsg::YourTurn {
+                recipient: player.address().clone(),
+                game: game.name().to_string(),
+                turn: game.turns(),
+            })
+            .map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Log attributes announcing the start of a turn, so clients can render its countdown
+fn turn_started_log(game: &Game) -> Vec<LogAttribute> {
+    let mut attributes = vec![log("turn", game.turns())];
+    if let Some(deadline) = game.turn_deadline() {
+        attributes.push(log("turn_deadline", deadline));
+    }
+    attributes
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::MyPasture { credentials } => try_get_my_pasture(&deps.storage, credentials),
+        QueryMsg::MyShots { credentials } => try_get_my_shots(&deps.storage, credentials),
+        QueryMsg::LastShot { credentials } => try_get_last_shot(&deps.storage, credentials),
+        QueryMsg::Version {} => to_binary(&version()),
+        QueryMsg::Presets {} => to_binary(&presets()),
+        QueryMsg::ValidatePasture { pasture, preset } => try_validate_pasture(&pasture, preset),
+        QueryMsg::TurnInfo { game } => try_get_turn_info(&deps.storage, game),
+        QueryMsg::TurnSummary { game } => try_get_turn_summary(&deps.storage, game),
+        QueryMsg::ShotInfo {
+            coords,
+            credentials,
+        } => try_get_shot_info(&deps.storage, coords, credentials),
+        QueryMsg::LegalActions { credentials } => try_get_legal_actions(&deps.storage, credentials),
+        QueryMsg::ObserverView { game, key } => try_get_observer_view(&deps.storage, game, &key),
+        QueryMsg::GameStatus { game } => try_get_game_status(&deps.storage, game),
+        QueryMsg::Rules { game } => to_binary(Game::load(&deps.storage, game.into())?.rules()),
+        QueryMsg::Templates { start_after, limit } => {
+            try_get_templates(&deps.storage, Pagination::new(start_after, limit))
+        }
+        QueryMsg::ListGames {
+            start_after,
+            limit,
+            status_filter,
+        } => {
+            let page = Pagination::new(start_after, limit);
+            try_list_games(&deps.storage, page, status_filter)
+        }
+        QueryMsg::Archive { start_after, limit } => {
+            let page = Pagination::new(start_after, limit);
+            to_binary(&archived_games(&deps.storage, page)?)
+        }
+        #[cfg(feature = "analysis")]
+        QueryMsg::Analysis { index, shots } => try_get_analysis(&deps.storage, index, shots),
+        QueryMsg::Transcript { game } => try_get_transcript(&deps.storage, &game),
+        QueryMsg::HeadToHead { a, b } => to_binary(&HeadToHead::load(&deps.storage, &a, &b)?),
+        QueryMsg::Sponsors { start_after, limit } => {
+            try_get_sponsors(&deps.storage, start_after, limit)
+        }
+        QueryMsg::Simulate {
+            msg,
+            sender,
+            sent_funds,
+            height,
+        } => try_simulate(&deps.storage, *msg, sender, sent_funds, height),
+    }
+}
+
+fn try_simulate<S: Storage>(
+    storage: &S,
+    msg: HandleMsg,
+    sender: HumanAddr,
+    sent_funds: Vec<Coin>,
+    height: u64,
+) -> StdResult<Binary> {
+    let env = Env {
+        block: BlockInfo {
+            height,
+            time: 0,
+            chain_id: String::new(),
+        },
+        message: MessageInfo { sender, sent_funds },
+        contract: ContractInfo {
+            address: HumanAddr::default(),
+        },
+        contract_key: None,
+        contract_code_hash: String::new(),
+    };
+
+    // Blind confirms would give the hidden shot away in the callbacks they trigger
+    if let HandleMsg::ConfirmBlind { .. } = msg {
+        return Err(StdError::generic_err(
+            "Blind confirms can't be simulated".to_string(),
+        ));
+    }
+
+    let response = match execute(&mut ScratchStorage::new(storage), env, msg) {
+        Ok(res) => SimulateResponse {
+            error: None,
+            messages: res.messages,
+            log: res.log,
+        },
+        Err(err) => SimulateResponse {
+            error: Some(err.to_string()),
+            messages: vec![],
+            log: vec![],
+        },
+    };
+
+    to_binary(&response)
+}
+
+fn try_get_my_pasture<S: Storage>(storage: &S, credentials: Credentials) -> StdResult<Binary> {
+    let game = Game::load(storage, credentials.game.clone().into())?.full()?;
+
+    let pasture = match game.role_of(&credentials)? {
+        Role::Player => game.player().pasture(),
+        Role::Opponent => game.opponent().pasture(),
+    };
+
+    to_binary(&pasture.view(game.board().size))
+}
+
+pub fn try_get_my_shots<S: Storage>(storage: &S, credentials: Credentials) -> StdResult<Binary> {
+    let game = Game::load(storage, credentials.game.clone().into())?.full()?;
+
+    let shots = match game.role_of(&credentials)? {
+        Role::Player => game.get_player_shots(),
+        Role::Opponent => game.get_opponent_shots(),
+    };
+
+    to_binary(&shots)
+}
+
+pub fn try_get_last_shot<S: Storage>(storage: &S, credentials: Credentials) -> StdResult<Binary> {
+    let game = Game::load(storage, credentials.game.clone().into())?.full()?;
+    let role = game.role_of(&credentials)?;
+
+    to_binary(&game.visible_shot(role))
+}
+
+fn try_get_shot_info<S: Storage>(
+    storage: &S,
+    coords: BoundedCoords,
+    credentials: Credentials,
+) -> StdResult<Binary> {
+    let game = Game::load(storage, credentials.game.clone().into())?.full()?;
+    let (own, target) = match game.role_of(&credentials)? {
+        Role::Player => (game.player(), game.opponent()),
+        Role::Opponent => (game.opponent(), game.player()),
+    };
+    let coords = coords.within(game.board().size)?;
+
+    to_binary(&ShotInfo {
+        received: own.pasture().shot_at(coords),
+        fired: target.pasture().shot_at(coords),
+    })
+}
+
+fn try_get_legal_actions<S: Storage>(storage: &S, credentials: Credentials) -> StdResult<Binary> {
+    let game = Game::load(storage, credentials.game.clone().into())?.full()?;
+    let role = game.role_of(&credentials)?;
+
+    to_binary(&game.legal_actions(role))
+}
+
+#[cfg(feature = "analysis")]
+fn try_get_analysis<S: Storage>(storage: &S, index: u32, shots: u32) -> StdResult<Binary> {
+    let game = archived_game(storage, index)?;
+    let analyse = |pasture: &Pasture| {
+        let constraints = Constraints::after_shots(pasture, shots as usize);
+        PastureAnalysis {
+            shots: constraints.shots(),
+            placements: remaining_placements(game.board.size, &constraints),
+            remaining_fleet: constraints.fleet,
+        }
+    };
+
+    to_binary(&AnalysisResponse {
+        winner: analyse(&game.winner.pasture),
+        loser: analyse(&game.loser.pasture),
+    })
+}
+
+fn try_get_transcript<S: Storage>(storage: &S, name: &str) -> StdResult<Binary> {
+    let (index, game) = archived_game_named(storage, name)?.ok_or_else(|| {
+        StdError::generic_err(format!("No finished game named {:?} was archived", name))
+    })?;
+
+    to_binary(&TranscriptResponse {
+        index,
+        digest: game.transcript_digest()?,
+        game,
+    })
+}
+
+fn version() -> VersionResponse {
+    let mut features = vec![];
+    if cfg!(feature = "backtraces") {
+        features.push("backtraces".to_string());
+    }
+    if cfg!(feature = "analysis") {
+        features.push("analysis".to_string());
+    }
+
+    VersionResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        state_version: STATE_VERSION,
+        rules_versions: (1..=RULES_VERSION).collect(),
+        features,
+    }
+}
+
+fn try_get_turn_info<S: Storage>(storage: &S, name: GameName) -> StdResult<Binary> {
+    let game = Game::load(storage, name.into())?.full()?;
+
+    to_binary(&TurnInfo {
+        turns: game.turns(),
+        player: game.player().username().to_string(),
+        shot_pending: game.next_shot().is_some(),
+        deadline: game.turn_deadline(),
+        rules: game.rules().clone(),
+    })
+}
+
+fn try_get_observer_view<S: Storage>(
+    storage: &S,
+    name: GameName,
+    key: &Password,
+) -> StdResult<Binary> {
+    let game = Game::load(storage, name.into())?.full()?;
+    if game.observer_key() != Some(key) {
+        return Err(StdError::unauthorized());
+    }
+
+    let size = game.board().size;
+    to_binary(&ObserverView {
+        sides: game
+            .players()
+            .iter()
+            .map(|player| ObservedSide {
+                username: player.username().to_string(),
+                pasture: player.pasture().view(size),
+            })
+            .collect(),
+    })
+}
+
+fn try_get_turn_summary<S: Storage>(storage: &S, name: GameName) -> StdResult<Binary> {
+    let game = Game::load(storage, name.into())?.full()?;
+    let players = game.players();
+
+    to_binary(&TurnSummary {
+        turns: game.turns(),
+        phase: game.phase(),
+        last_action: players.iter().filter_map(Player::last_action).max(),
+        herds_left: players
+            .iter()
+            .map(|player| player.pasture().herds_left())
+            .collect(),
+    })
+}
+
+fn try_get_game_status<S: Storage>(storage: &S, name: GameName) -> StdResult<Binary> {
+    let game = Game::load(storage, name.into())?;
+    let players = game.players();
+    let sides = players
+        .iter()
+        .enumerate()
+        .map(|(seat, player)| SideStatus {
+            username: player.username().to_string(),
+            sunk_herds: player.pasture().sunk_herds(),
+            remaining_fleet: player.pasture().remaining_fleet(),
+            points: match (game.rules().victory, players.get((seat + 1) % 2)) {
+                (VictoryCondition::PointsAfterRounds { .. }, Some(target)) => {
+                    Some(target.pasture().points_conceded())
+                }
+                _ => None,
+            },
+            comment: player.comment().map(ToString::to_string),
+            ammo: player.ammo(),
+        })
+        .collect();
+
+    to_binary(&GameStatus {
+        turns: game.turns(),
+        sides,
+        winner: game.winner().map(|player| player.username().to_string()),
+        locale: game.locale().map(ToString::to_string),
+        wager: game.wager().cloned(),
+    })
+}
+
+fn try_list_games<S: Storage>(
+    storage: &S,
+    page: Pagination,
+    status_filter: Option<LobbyStatus>,
+) -> StdResult<Binary> {
+    let indexes = list_page(storage, page)?;
+    let last_index = indexes.clone().last();
+
+    let mut games = vec![];
+    for index in indexes {
+        // Removed games leave a gap in the list
+        if let Some(name) = listed_game(storage, index)? {
+            let game = Game::load(storage, name.clone())?;
+            let status = if game.winner().is_some() {
+                LobbyStatus::Finished
+            } else if game.is_full() {
+                LobbyStatus::Running
+            } else {
+                LobbyStatus::Open
+            };
+            if status_filter.is_none() || status_filter == Some(status) {
+                games.push(GameListing {
+                    index,
+                    name,
+                    players: game.player_count(),
+                    status,
+                    board: game.board().clone(),
+                });
+            }
+        }
+    }
+
+    to_binary(&ListGamesResponse { games, last_index })
+}
+
+fn try_validate_pasture(pasture: &Pasture, preset_name: Option<String>) -> StdResult<Binary> {
+    let board = preset(preset_name.as_deref().unwrap_or(DEFAULT_PRESET))?;
+
+    to_binary(&pasture.violations(&board))
+}
+
+fn try_get_templates<S: Storage>(storage: &S, page: Pagination) -> StdResult<Binary> {
+    let indexes = template_page(storage, page)?;
+    let last_index = indexes.clone().last();
+
+    let mut templates = vec![];
+    for index in indexes {
+        // Deleted templates leave a gap in the list
+        if let Some(template) = listed_template(storage, index)? {
+            templates.push(template);
+        }
+    }
+
+    to_binary(&TemplatesResponse {
+        templates,
+        last_index,
+    })
+}
+
+fn try_get_sponsors<S: Storage>(
+    storage: &S,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let treasury = Treasury::load(storage)?;
+    let sponsorships = treasury.sponsorships(storage, Pagination::new(start_after, limit))?;
+
+    to_binary(&SponsorsResponse {
+        balance: treasury.balance,
+        forfeited: treasury.forfeited,
+        sponsorships,
+    })
+}
+
+/// A running game, loaded for a move of one of its players together with the contract
+/// settings `execute` loaded for the message
+struct GameCtx<'a> {
+    config: &'a Config,
+    game: FullGame,
+}
+
+impl<'a> GameCtx<'a> {
+    /// Load the game these credentials are for, and reject it if it expired or the move
+    /// came from the wrong address
+    fn load<S: Storage>(
+        storage: &S,
+        env: &Env,
+        credentials: &Credentials,
+        config: &'a Config,
+    ) -> StdResult<Self> {
+        let game = Game::load(storage, credentials.game.clone().into())?.full()?;
+        game.check_expiry(env.block.height)?;
+        game.check_sender(credentials, &env.message.sender)?;
+
+        Ok(Self { config, game })
+    }
+
+    /// Reject players who already acted in the game within the configured cooldown
+    fn rate_limit(&mut self, env: &Env, credentials: &Credentials) -> StdResult<()> {
+        let cooldown = self.config.action_cooldown;
+        self.game
+            .record_action(credentials, env.block.height, cooldown)
+    }
+
+    /// Attach a public comment to the shot being fired, unless it contains a banned word
+    fn comment(&mut self, comment: Option<Comment>) -> StdResult<()> {
+        if let Some(comment) = &comment {
+            self.config.check_words(comment)?;
+        }
+        self.game.set_comment(comment);
+
+        Ok(())
+    }
+}
+
+impl Deref for GameCtx<'_> {
+    type Target = FullGame;
+    fn deref(&self) -> &Self::Target {
+        &self.game
+    }
+}
+
+impl DerefMut for GameCtx<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.game
+    }
+}
+
+/// Check that the credentials belong to a player who may take this action now
+fn authorize(game: &FullGame, credentials: &Credentials, action: ActionKind) -> StdResult<Role> {
+    let role = game.role_of(credentials)?;
+    game.check_action(role, action)?;
+    Ok(role)
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{coin, coins, from_binary, from_slice, to_vec, HumanAddr, StdError};
+
+    use crate::bounded::{Password, Username};
+    use crate::msg::{CellState, Deprecation, GameStats, MyPastureResponse, ShotRecord, Shots};
+    use crate::practice::TUTOR;
+    use crate::presets::{FleetEntry, MAX_PASTURE_SIZE};
+    use crate::rules::SuddenDeath;
+    use crate::state::{shot_commitment, Herd, Orientation, PlacementViolation};
+
+    use super::*;
+
+    fn credentials(username: &str) -> Credentials {
+        Credentials {
+            game: "pasture party".parse().unwrap(),
+            username: username.parse().unwrap(),
+            password: format!("{}'s password", username).parse().unwrap(),
+        }
+    }
+
+    fn pasture() -> Pasture {
+        Pasture::new(
+            vec![
+                Herd::new(0, 0, 2, Orientation::Horizontal),
+                Herd::new(0, 2, 3, Orientation::Horizontal),
+                Herd::new(0, 4, 3, Orientation::Horizontal),
+                Herd::new(0, 6, 4, Orientation::Horizontal),
+                Herd::new(0, 8, 5, Orientation::Horizontal),
+            ],
+            vec![],
+        )
+    }
+
+    /// The fields of `HandleMsg::NewGame`, defaulting to a plain game named "pasture party",
+    /// so tests only spell out the fields they are about
+    struct NewGame {
+        name: GameName,
+        preset: Option<String>,
+        size: Option<u8>,
+        rules: GameRules,
+        callback: Option<Callback>,
+        lifetime: Option<u64>,
+        locale: Option<String>,
+        wager: Option<Coin>,
+    }
+
+    impl Default for NewGame {
+        fn default() -> Self {
+            Self {
+                name: "pasture party".parse().unwrap(),
+                preset: None,
+                size: None,
+                rules: GameRules::default(),
+                callback: None,
+                lifetime: None,
+                locale: None,
+                wager: None,
+            }
+        }
+    }
+
+    impl NewGame {
+        fn msg(self) -> HandleMsg {
+            HandleMsg::NewGame {
+                name: self.name,
+                preset: self.preset,
+                size: self.size,
+                rules: self.rules,
+                callback: self.callback,
+                lifetime: self.lifetime,
+                locale: self.locale,
+                wager: self.wager,
+            }
+        }
+    }
+
+    /// The settings most tests run the contract with
+    fn init_msg() -> InitMsg {
+        InitMsg {
+            game_lifetime: None,
+            // Most tests act several times in the same block
+            action_cooldown: Some(0),
+            mailbox: None,
+            lobby_deposit: None,
+            banned_words: vec![],
+            allowed_callbacks: None,
+            deprecations: vec![],
+        }
+    }
+
+    fn start_game<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        start_game_with(deps, NewGame::default().msg());
+    }
+
+    fn init_contract<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>) {
+        init(deps, mock_env("admin", &[]), init_msg()).unwrap();
+    }
+
+    fn start_game_with<S: Storage, A: Api, Q: Querier>(deps: &mut Extern<S, A, Q>, msg: HandleMsg) {
+        init_contract(deps);
+        handle(deps, mock_env("alice", &[]), msg).unwrap();
+        for username in &["alice", "bob"] {
+            let msg = HandleMsg::Join {
+                pasture: pasture(),
+                credentials: credentials(username),
+            };
+            handle(deps, mock_env(*username, &[]), msg).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_herd_serialize() {
+        let serialized =
+            "{\"orientation\": \"horizontal\", \"length\": 3, \"coords\": {\"x\": 2, \"y\": 4}}"
+                .as_bytes();
+        let herd: Herd = from_slice(serialized).unwrap();
+        println!("{:?}", herd);
 
         let herd = Herd::new(4, 6, 3, Orientation::Vertical);
         let serialized = to_vec(&herd).unwrap();
         let serialized = String::from_utf8_lossy(&serialized);
         println!("{:?}", serialized);
     }
+
+    #[test]
+    fn test_role_of() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+
+        let game = Game::load(&deps.storage, "pasture party".to_string())
+            .unwrap()
+            .full()
+            .unwrap();
+        assert_eq!(game.role_of(&credentials("alice")).unwrap(), Role::Player);
+        assert_eq!(game.role_of(&credentials("bob")).unwrap(), Role::Opponent);
+
+        let mut impostor = credentials("alice");
+        impostor.password = "hunter2".parse().unwrap();
+        match game.role_of(&impostor) {
+            Err(StdError::Unauthorized { .. }) => {}
+            other => panic!("Expected unauthorized, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_turn_order() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+
+        let shoot = |username: &str| HandleMsg::Shoot {
+            coords: Coords::new(1, 0).into(),
+            comment: None,
+            credentials: credentials(username),
+        };
+        let confirm = |username: &str| HandleMsg::Confirm {
+            coords: Coords::new(1, 0).into(),
+            credentials: credentials(username),
+        };
+
+        handle(&mut deps, mock_env("bob", &[]), shoot("bob")).unwrap_err();
+        // There's nothing to confirm yet
+        handle(&mut deps, mock_env("bob", &[]), confirm("bob")).unwrap_err();
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice")).unwrap();
+        // The pending shot can't be replaced
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice")).unwrap_err();
+        handle(&mut deps, mock_env("alice", &[]), confirm("alice")).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), confirm("bob")).unwrap();
+
+        // Now it's bob's turn
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice")).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), shoot("bob")).unwrap();
+    }
+
+    #[test]
+    fn test_shoot_again_on_hit() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let msg = HandleMsg::SaveTemplate {
+            name: "sharpshooters".parse().unwrap(),
+            board: BoardConfig::default(),
+            rules: GameRules {
+                shoot_again_on_hit: true,
+                ..GameRules::default()
+            },
+            lifetime: None,
+        };
+        handle(&mut deps, mock_env("organizer", &[]), msg).unwrap();
+        let msg = HandleMsg::NewGameFromTemplate {
+            template: "sharpshooters".parse().unwrap(),
+            name: "pasture party".parse().unwrap(),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        for username in &["alice", "bob"] {
+            let msg = HandleMsg::Join {
+                pasture: pasture(),
+                credentials: credentials(username),
+            };
+            handle(&mut deps, mock_env(*username, &[]), msg).unwrap();
+        }
+
+        let shoot = |username: &str, x| HandleMsg::Shoot {
+            coords: Coords::new(x, 0).into(),
+            comment: None,
+            credentials: credentials(username),
+        };
+        let confirm = |username: &str, x| HandleMsg::Confirm {
+            coords: Coords::new(x, 0).into(),
+            credentials: credentials(username),
+        };
+
+        // A hit lets alice shoot again
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice", 1)).unwrap();
+        handle(&mut deps, mock_env("bob", &[]), confirm("bob", 1)).unwrap();
+        handle(&mut deps, mock_env("bob", &[]), shoot("bob", 9)).unwrap_err();
+
+        // A miss passes the turn to bob
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice", 9)).unwrap();
+        handle(&mut deps, mock_env("bob", &[]), confirm("bob", 9)).unwrap();
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice", 8)).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), shoot("bob", 9)).unwrap();
+    }
+
+    #[test]
+    fn test_takeback() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+
+        let shoot = |x| HandleMsg::Shoot {
+            coords: Coords::new(x, 0).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        let request = |username: &str| HandleMsg::RequestTakeback {
+            credentials: credentials(username),
+        };
+        let grant = |username: &str| HandleMsg::GrantTakeback {
+            credentials: credentials(username),
+        };
+
+        handle(&mut deps, mock_env("alice", &[]), request("alice")).unwrap_err();
+        handle(&mut deps, mock_env("alice", &[]), shoot(9)).unwrap();
+        handle(&mut deps, mock_env("bob", &[]), grant("bob")).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), request("bob")).unwrap_err();
+        handle(&mut deps, mock_env("alice", &[]), request("alice")).unwrap();
+        handle(&mut deps, mock_env("alice", &[]), grant("alice")).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), grant("bob")).unwrap();
+
+        // It's still alice's turn, and she can pick another shot
+        let game = Game::load(&deps.storage, "pasture party".to_string())
+            .unwrap()
+            .full()
+            .unwrap();
+        assert_eq!(game.next_shot(), None);
+        assert_eq!(game.turns(), 0);
+        handle(&mut deps, mock_env("alice", &[]), shoot(1)).unwrap();
+    }
+
+    #[test]
+    fn test_observer_key() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+
+        let key: Password = "stream key".parse().unwrap();
+        let allow = |username: &str, key: &Password| HandleMsg::AllowObserver {
+            key: key.clone(),
+            credentials: credentials(username),
+        };
+        let revoke = |username: &str| HandleMsg::RevokeObserver {
+            credentials: credentials(username),
+        };
+        let watch = |deps: &Extern<_, _, _>| {
+            let msg = QueryMsg::ObserverView {
+                game: "pasture party".parse().unwrap(),
+                key: key.clone(),
+            };
+            query(deps, msg)
+        };
+
+        // The key only works once both players consented to it
+        handle(&mut deps, mock_env("alice", &[]), allow("alice", &key)).unwrap();
+        watch(&deps).unwrap_err();
+        let other_key = "another key".parse().unwrap();
+        handle(&mut deps, mock_env("bob", &[]), allow("bob", &other_key)).unwrap();
+        watch(&deps).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), allow("bob", &key)).unwrap();
+
+        let view: ObserverView = from_binary(&watch(&deps).unwrap()).unwrap();
+        let usernames: Vec<_> = view
+            .sides
+            .iter()
+            .map(|side| side.username.as_str())
+            .collect();
+        assert_eq!(usernames, vec!["alice", "bob"]);
+        assert_eq!(view.sides[1].pasture, pasture().view(10));
+
+        // Either player can stop it from working
+        handle(&mut deps, mock_env("bob", &[]), revoke("bob")).unwrap();
+        match watch(&deps) {
+            Err(StdError::Unauthorized { .. }) => {}
+            other => panic!("Expected an unauthorized error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_callback_notified() {
+        let mut deps = mock_dependencies(20, &[]);
+        let callback = Callback {
+            contract_addr: HumanAddr::from("referee"),
+            code_hash: "referee code hash".to_string(),
+        };
+        start_game_with(
+            &mut deps,
+            NewGame {
+                callback: Some(callback.clone()),
+                ..NewGame::default()
+            }
+            .msg(),
+        );
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(1, 0).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(1, 0).into(),
+            credentials: credentials("bob"),
+        };
+        let res = handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+
+        let expected = callback
+            .notify(&CallbackMsg::TurnResolved {
+                game: "pasture party".to_string(),
+                turn: 1,
+                coords: Coords::new(1, 0),
+                hit: true,
+            })
+            .unwrap();
+        assert_eq!(res.messages, vec![expected]);
+    }
+
+    #[test]
+    fn test_allowed_callbacks() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg {
+            allowed_callbacks: Some(vec![HumanAddr::from("referee")]),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("admin", &[]), msg).unwrap();
+        let new_game = |contract_addr: &str| {
+            NewGame {
+                callback: Some(Callback {
+                    contract_addr: HumanAddr::from(contract_addr),
+                    code_hash: "code hash".to_string(),
+                }),
+                ..NewGame::default()
+            }
+            .msg()
+        };
+
+        match handle(&mut deps, mock_env("alice", &[]), new_game("heckler")) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Games can't send callbacks to heckler");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("alice", &[]), new_game("referee")).unwrap();
+    }
+
+    #[test]
+    fn test_preset_fleet() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let new_game = |size| {
+            NewGame {
+                preset: Some("mini".to_string()),
+                size,
+                ..NewGame::default()
+            }
+            .msg()
+        };
+        match handle(&mut deps, mock_env("alice", &[]), new_game(Some(6))) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Pick either a preset or a pasture size");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("alice", &[]), new_game(None)).unwrap();
+
+        // The classic fleet doesn't fit in the mini pasture
+        let msg = HandleMsg::Join {
+            pasture: pasture(),
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap_err();
+
+        let mini_pasture = Pasture::new(
+            vec![
+                Herd::new(0, 0, 2, Orientation::Horizontal),
+                Herd::new(0, 2, 3, Orientation::Horizontal),
+                Herd::new(0, 4, 4, Orientation::Horizontal),
+            ],
+            vec![],
+        );
+        let msg = HandleMsg::Join {
+            pasture: mini_pasture,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_confirm_pending_shot() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+        let shoot = |username: &str, x| HandleMsg::Shoot {
+            coords: Coords::new(x, 0).into(),
+            comment: None,
+            credentials: credentials(username),
+        };
+        let confirm = |username: &str, x| HandleMsg::Confirm {
+            coords: Coords::new(x, 0).into(),
+            credentials: credentials(username),
+        };
+        let expect_refusal = |res: StdResult<HandleResponse>| match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "You can only confirm the pending shot");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        };
+
+        // Bob can't pass off a hit on his sheep as a miss on an empty cell
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice", 1)).unwrap();
+        expect_refusal(handle(&mut deps, mock_env("bob", &[]), confirm("bob", 5)));
+        handle(&mut deps, mock_env("bob", &[]), confirm("bob", 1)).unwrap();
+
+        // Nor confirm a cell that was already shot instead of the pending one
+        handle(&mut deps, mock_env("bob", &[]), shoot("bob", 9)).unwrap();
+        handle(&mut deps, mock_env("alice", &[]), confirm("alice", 9)).unwrap();
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice", 0)).unwrap();
+        expect_refusal(handle(&mut deps, mock_env("bob", &[]), confirm("bob", 1)));
+
+        let game = Game::load(&deps.storage, "pasture party".to_string())
+            .unwrap()
+            .full()
+            .unwrap();
+        assert_eq!(game.next_shot(), Some(Coords::new(0, 0)));
+        assert_eq!(game.get_player_shots().hits, vec![Coords::new(1, 0)]);
+    }
+
+    #[test]
+    fn test_shots_recorded_on_target() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(1, 0).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(1, 0).into(),
+            credentials: credentials("bob"),
+        };
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+
+        let my_shots = |username: &str| -> Shots {
+            let msg = QueryMsg::MyShots {
+                credentials: credentials(username),
+            };
+            from_binary(&query(&deps, msg).unwrap()).unwrap()
+        };
+        let alice_shots = my_shots("alice");
+        assert_eq!(alice_shots.hits, vec![Coords::new(1, 0)]);
+        assert!(alice_shots.misses.is_empty());
+        let bob_shots = my_shots("bob");
+        assert!(bob_shots.hits.is_empty());
+        assert!(bob_shots.misses.is_empty());
+
+        let msg = QueryMsg::MyPasture {
+            credentials: credentials("bob"),
+        };
+        let bob_pasture: MyPastureResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(bob_pasture.cells[0][1], CellState::OwnSheepHit);
+        assert_eq!(bob_pasture.cells[0][0], CellState::OwnSheep);
+        assert_eq!(bob_pasture.cells[0][2], CellState::Unknown);
+
+        let shot_info = |username: &str, x| -> ShotInfo {
+            let msg = QueryMsg::ShotInfo {
+                coords: Coords::new(x, 0).into(),
+                credentials: credentials(username),
+            };
+            from_binary(&query(&deps, msg).unwrap()).unwrap()
+        };
+        let record = ShotRecord {
+            number: 1,
+            state: CellState::Hit,
+        };
+        assert_eq!(
+            shot_info("alice", 1),
+            ShotInfo {
+                received: None,
+                fired: Some(record),
+            }
+        );
+        assert_eq!(
+            shot_info("bob", 1),
+            ShotInfo {
+                received: Some(record),
+                fired: None,
+            }
+        );
+        assert_eq!(
+            shot_info("bob", 2),
+            ShotInfo {
+                received: None,
+                fired: None,
+            }
+        );
+
+        // Bob misses, and alice sinks the herd she hit
+        for &(shooter, target, coords) in &[
+            ("bob", "alice", Coords::new(9, 9)),
+            ("alice", "bob", Coords::new(0, 0)),
+        ] {
+            let msg = HandleMsg::Shoot {
+                coords: coords.into(),
+                comment: None,
+                credentials: credentials(shooter),
+            };
+            handle(&mut deps, mock_env(shooter, &[]), msg).unwrap();
+            let msg = HandleMsg::Confirm {
+                coords: coords.into(),
+                credentials: credentials(target),
+            };
+            handle(&mut deps, mock_env(target, &[]), msg).unwrap();
+        }
+        let my_pasture = |username: &str| -> MyPastureResponse {
+            let msg = QueryMsg::MyPasture {
+                credentials: credentials(username),
+            };
+            from_binary(&query(&deps, msg).unwrap()).unwrap()
+        };
+        assert_eq!(my_pasture("alice").cells[9][9], CellState::Miss);
+        assert_eq!(my_pasture("bob").cells[0][1], CellState::Sunk);
+        let msg = QueryMsg::ShotInfo {
+            coords: Coords::new(1, 0).into(),
+            credentials: credentials("alice"),
+        };
+        let info: ShotInfo = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(info.fired.unwrap().state, CellState::Sunk);
+    }
+
+    #[test]
+    fn test_turn_deadline() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let msg = NewGame {
+            rules: GameRules {
+                turn_clock: Some(5),
+                ..GameRules::default()
+            },
+            ..NewGame::default()
+        }
+        .msg();
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let mut res = HandleResponse::default();
+        for username in &["alice", "bob"] {
+            let msg = HandleMsg::Join {
+                pasture: pasture(),
+                credentials: credentials(username),
+            };
+            res = handle(&mut deps, mock_env(*username, &[]), msg).unwrap();
+        }
+
+        let deadline = mock_env("bob", &[]).block.height + 5;
+        assert_eq!(
+            res.log,
+            vec![log("turn", 0), log("turn_deadline", deadline)]
+        );
+
+        let msg = QueryMsg::TurnInfo {
+            game: "pasture party".parse().unwrap(),
+        };
+        let turn_info: TurnInfo = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(
+            turn_info,
+            TurnInfo {
+                turns: 0,
+                player: "alice".to_string(),
+                shot_pending: false,
+                deadline: Some(deadline),
+                rules: GameRules {
+                    version: RULES_VERSION,
+                    turn_clock: Some(5),
+                    ..GameRules::default()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_turn_summary() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+        let summary = |deps: &Extern<_, _, _>| -> TurnSummary {
+            let msg = QueryMsg::TurnSummary {
+                game: "pasture party".parse().unwrap(),
+            };
+            from_binary(&query(deps, msg).unwrap()).unwrap()
+        };
+
+        let herds = pasture().herds_left();
+        assert_eq!(
+            summary(&deps),
+            TurnSummary {
+                turns: 0,
+                phase: Phase::Shooting,
+                last_action: None,
+                herds_left: vec![herds, herds],
+            }
+        );
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(9, 9).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        let mut env = mock_env("alice", &[]);
+        env.block.height = 12_400;
+        handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            summary(&deps),
+            TurnSummary {
+                turns: 0,
+                phase: Phase::Confirming,
+                last_action: Some(12_400),
+                herds_left: vec![herds, herds],
+            }
+        );
+    }
+
+    #[test]
+    fn test_legal_actions() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+        let legal_actions = |deps: &Extern<_, _, _>, username| -> Vec<ActionKind> {
+            let msg = QueryMsg::LegalActions {
+                credentials: credentials(username),
+            };
+            from_binary(&query(deps, msg).unwrap()).unwrap()
+        };
+
+        assert_eq!(legal_actions(&deps, "alice"), vec![ActionKind::Shoot]);
+        assert_eq!(legal_actions(&deps, "bob"), vec![]);
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(9, 9).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        assert_eq!(
+            legal_actions(&deps, "alice"),
+            vec![ActionKind::RequestTakeback]
+        );
+        assert_eq!(legal_actions(&deps, "bob"), vec![ActionKind::Confirm]);
+
+        let msg = HandleMsg::RequestTakeback {
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        assert_eq!(
+            legal_actions(&deps, "bob"),
+            vec![ActionKind::Confirm, ActionKind::GrantTakeback]
+        );
+
+        let msg = QueryMsg::LegalActions {
+            credentials: Credentials {
+                password: "wrong".parse().unwrap(),
+                ..credentials("bob")
+            },
+        };
+        match query(&deps, msg) {
+            Err(StdError::Unauthorized { .. }) => {}
+            other => panic!("Expected unauthorized, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strict_confirms() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = NewGame {
+            rules: GameRules {
+                strict_confirms: true,
+                ..GameRules::default()
+            },
+            ..NewGame::default()
+        }
+        .msg();
+        start_game_with(&mut deps, msg);
+        let last_shot = |deps: &Extern<_, _, _>, username| -> Option<Coords> {
+            let msg = QueryMsg::LastShot {
+                credentials: credentials(username),
+            };
+            from_binary(&query(deps, msg).unwrap()).unwrap()
+        };
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(1, 0).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+
+        // Only the shooter gets to see the pending shot
+        assert_eq!(last_shot(&deps, "alice"), Some(Coords::new(1, 0)));
+        assert_eq!(last_shot(&deps, "bob"), None);
+        let msg = QueryMsg::LegalActions {
+            credentials: credentials("bob"),
+        };
+        let actions: Vec<ActionKind> = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(actions, vec![ActionKind::ConfirmBlind]);
+
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(1, 0).into(),
+            credentials: credentials("bob"),
+        };
+        match handle(&mut deps, mock_env("bob", &[]), msg) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+                msg,
+                "The pending shot is hidden in this game, so confirm it blindly"
+            ),
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        let confirm = HandleMsg::ConfirmBlind {
+            credentials: credentials("bob"),
+        };
+        let msg = QueryMsg::Simulate {
+            msg: Box::new(confirm.clone()),
+            sender: HumanAddr::from("bob"),
+            sent_funds: vec![],
+            height: 12_345,
+        };
+        query(&deps, msg).unwrap_err();
+        let msg = HandleMsg::ConfirmBlind {
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), confirm).unwrap();
+
+        let msg = QueryMsg::MyShots {
+            credentials: credentials("alice"),
+        };
+        let shots: Shots = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(shots.hits, vec![Coords::new(1, 0)]);
+    }
+
+    #[test]
+    fn test_emote() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+        let emote = |username: &str| HandleMsg::Emote {
+            emote: Emote::GoodGame,
+            credentials: credentials(username),
+        };
+
+        let res = handle(&mut deps, mock_env("alice", &[]), emote("alice")).unwrap();
+        assert_eq!(
+            res.log,
+            vec![log("emote", "good_game"), log("player", "alice")]
+        );
+
+        match handle(&mut deps, mock_env("bob", &[]), emote("bob")) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "You can only emote during your own turn");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_list_games() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+        for &(name, size) in &[("open field", None), ("closed barn", Some(12))] {
+            let msg = NewGame {
+                name: name.parse().unwrap(),
+                size,
+                ..NewGame::default()
+            }
+            .msg();
+            handle(&mut deps, mock_env("carol", &[]), msg).unwrap();
+        }
+        let list = |deps: &Extern<_, _, _>, start_after, status_filter| -> ListGamesResponse {
+            let msg = QueryMsg::ListGames {
+                start_after,
+                limit: Some(2),
+                status_filter,
+            };
+            from_binary(&query(deps, msg).unwrap()).unwrap()
+        };
+
+        assert_eq!(
+            list(&deps, None, None),
+            ListGamesResponse {
+                games: vec![
+                    GameListing {
+                        index: 0,
+                        name: "pasture party".to_string(),
+                        players: 2,
+                        status: LobbyStatus::Running,
+                        board: BoardConfig::default(),
+                    },
+                    GameListing {
+                        index: 1,
+                        name: "open field".to_string(),
+                        players: 0,
+                        status: LobbyStatus::Open,
+                        board: BoardConfig::default(),
+                    },
+                ],
+                last_index: Some(1),
+            }
+        );
+        let page = list(&deps, Some(1), None);
+        assert_eq!(page.games.len(), 1);
+        assert_eq!(page.games[0].name, "closed barn");
+        // Games created with only a pasture size get a fleet to match
+        assert_eq!(
+            page.games[0].board,
+            BoardConfig {
+                size: 12,
+                fleet: vec![
+                    FleetEntry {
+                        length: 2,
+                        count: 2
+                    },
+                    FleetEntry {
+                        length: 3,
+                        count: 2
+                    },
+                    FleetEntry {
+                        length: 4,
+                        count: 1
+                    },
+                    FleetEntry {
+                        length: 5,
+                        count: 2
+                    },
+                ],
+            }
+        );
+        assert_eq!(fleet_for_board(10), BoardConfig::default().fleet);
+        assert_eq!(list(&deps, Some(2), None).last_index, None);
+
+        let open = list(&deps, None, Some(LobbyStatus::Open));
+        assert_eq!(open.games.len(), 1);
+        assert_eq!(open.last_index, Some(1));
+    }
+
+    #[test]
+    fn test_templates() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let save = |turn_clock| HandleMsg::SaveTemplate {
+            name: "league night".parse().unwrap(),
+            board: preset("mini").unwrap(),
+            rules: GameRules {
+                turn_clock,
+                ..GameRules::default()
+            },
+            lifetime: None,
+        };
+        handle(&mut deps, mock_env("organizer", &[]), save(Some(10))).unwrap();
+        match handle(&mut deps, mock_env("intruder", &[]), save(Some(1))) {
+            Err(StdError::Unauthorized { .. }) => {}
+            other => panic!("Expected unauthorized, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("organizer", &[]), save(Some(0))).unwrap_err();
+        handle(&mut deps, mock_env("organizer", &[]), save(Some(20))).unwrap();
+
+        let msg = HandleMsg::SaveTemplate {
+            name: "weekend cup".parse().unwrap(),
+            board: preset("mini").unwrap(),
+            rules: GameRules::default(),
+            lifetime: None,
+        };
+        handle(&mut deps, mock_env("organizer", &[]), msg).unwrap();
+        let templates = QueryMsg::Templates {
+            start_after: None,
+            limit: Some(1),
+        };
+        let res: TemplatesResponse = from_binary(&query(&deps, templates).unwrap()).unwrap();
+        assert_eq!(res.last_index, Some(0));
+        assert_eq!(res.templates.len(), 1);
+        assert_eq!(res.templates[0].rules.turn_clock, Some(20));
+        let templates = QueryMsg::Templates {
+            start_after: res.last_index,
+            limit: None,
+        };
+        let res: TemplatesResponse = from_binary(&query(&deps, templates).unwrap()).unwrap();
+        assert_eq!(res.templates.len(), 1);
+        assert_eq!(res.templates[0].name, "weekend cup");
+
+        let msg = HandleMsg::NewGameFromTemplate {
+            template: "league night".parse().unwrap(),
+            name: "pasture party".parse().unwrap(),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let game = Game::load(&deps.storage, "pasture party".to_string()).unwrap();
+        assert_eq!(game.board(), &preset("mini").unwrap());
+
+        let msg = QueryMsg::Rules {
+            game: "pasture party".parse().unwrap(),
+        };
+        let rules: GameRules = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(rules.turn_clock, Some(20));
+
+        let msg = HandleMsg::DeleteTemplate {
+            name: "league night".parse().unwrap(),
+        };
+        handle(&mut deps, mock_env("intruder", &[]), msg.clone()).unwrap_err();
+        handle(&mut deps, mock_env("organizer", &[]), msg).unwrap();
+        let templates = QueryMsg::Templates {
+            start_after: None,
+            limit: None,
+        };
+        let res: TemplatesResponse = from_binary(&query(&deps, templates).unwrap()).unwrap();
+        assert_eq!(res.templates.len(), 1);
+        assert_eq!(res.last_index, Some(1));
+    }
+
+    #[test]
+    fn test_donations() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+
+        handle(&mut deps, mock_env("stingy", &[]), HandleMsg::Donate {}).unwrap_err();
+        for sponsor in &["generous", "lavish"] {
+            let env = mock_env(*sponsor, &coins(100, "uscrt"));
+            handle(&mut deps, env, HandleMsg::Donate {}).unwrap();
+        }
+
+        let msg = QueryMsg::Sponsors {
+            start_after: Some(0),
+            limit: None,
+        };
+        let res: SponsorsResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(res.balance, coins(200, "uscrt"));
+        assert_eq!(res.sponsorships.len(), 1);
+        assert_eq!(res.sponsorships[0].sponsor, HumanAddr::from("lavish"));
+    }
+
+    #[test]
+    fn test_unexpected_funds() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let env = || mock_env("alice", &coins(100, "uscrt"));
+
+        // Nothing takes funds that it has no use for
+        let msg = HandleMsg::CancelGame {
+            name: "pasture party".parse().unwrap(),
+        };
+        match handle(&mut deps, env(), msg) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "The cancel_game message doesn't take any funds");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        match handle(&mut deps, env(), NewGame::default().msg()) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Opening a lobby takes no deposit");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("alice", &[]), NewGame::default().msg()).unwrap();
+        let msg = HandleMsg::Join {
+            pasture: pasture(),
+            credentials: credentials("alice"),
+        };
+        match handle(&mut deps, env(), msg) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "This game isn't played for a wager");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_game_expiry() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let msg = NewGame {
+            lifetime: Some(10),
+            ..NewGame::default()
+        }
+        .msg();
+        let env = mock_env("alice", &[]);
+        let created_at = env.block.height;
+        handle(&mut deps, env, msg).unwrap();
+
+        let join = |username: &str| HandleMsg::Join {
+            pasture: pasture(),
+            credentials: credentials(username),
+        };
+        let mut env = mock_env("alice", &[]);
+        env.block.height = created_at + 9;
+        handle(&mut deps, env, join("alice")).unwrap();
+
+        let mut env = mock_env("bob", &[]);
+        env.block.height = created_at + 10;
+        handle(&mut deps, env, join("bob")).unwrap_err();
+    }
+
+    #[test]
+    fn test_queries_reject_strangers() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+
+        let queries = vec![
+            QueryMsg::MyPasture {
+                credentials: credentials("eve"),
+            },
+            QueryMsg::MyShots {
+                credentials: credentials("eve"),
+            },
+            QueryMsg::LastShot {
+                credentials: credentials("eve"),
+            },
+        ];
+        for msg in queries {
+            match query(&deps, msg) {
+                Err(StdError::Unauthorized { .. }) => {}
+                other => panic!("Expected unauthorized, got {:?}", other),
+            }
+        }
+
+        let res = query(
+            &deps,
+            QueryMsg::MyPasture {
+                credentials: credentials("bob"),
+            },
+        )
+        .unwrap();
+        let my_pasture: MyPastureResponse = from_binary(&res).unwrap();
+        assert_eq!(my_pasture.herds, pasture().herds());
+    }
+
+    #[test]
+    fn test_oversized_credentials() {
+        let credentials = |username: &str| {
+            format!(
+                r#"{{"game":"pasture party","username":"{}","password":"hunter2"}}"#,
+                username
+            )
+        };
+        let username = "a".repeat(Username::MAX_LEN);
+        let parsed: Credentials = from_slice(credentials(&username).as_bytes()).unwrap();
+        assert_eq!(parsed.username.as_str(), username);
+
+        let username = "a".repeat(Username::MAX_LEN + 1);
+        match from_slice::<Credentials>(credentials(&username).as_bytes()) {
+            Err(StdError::ParseErr { .. }) => {}
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+        assert!(username.parse::<Username>().is_err());
+    }
+
+    #[test]
+    fn test_shots_out_of_bounds() {
+        let coords = |x| format!(r#"{{"x":{},"y":0}}"#, x);
+        from_slice::<BoundedCoords>(coords(MAX_PASTURE_SIZE - 1).as_bytes()).unwrap();
+        match from_slice::<BoundedCoords>(coords(MAX_PASTURE_SIZE).as_bytes()) {
+            Err(StdError::ParseErr { .. }) => {}
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+
+        // Coordinates that fit the largest pasture are still checked against the game's
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+        let shoot = |x| HandleMsg::Shoot {
+            coords: Coords::new(x, 0).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        match handle(&mut deps, mock_env("alice", &[]), shoot(10)) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "(10, 0) lies outside the pasture");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("alice", &[]), shoot(9)).unwrap();
+    }
+
+    #[test]
+    fn test_practice_game() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let msg = HandleMsg::NewPracticeGame {
+            pasture: pasture(),
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+
+        let shoot = |x, y| HandleMsg::Shoot {
+            coords: Coords::new(x, y).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), shoot(1, 1)).unwrap();
+        assert_eq!(
+            &res.log[..2],
+            &[log("hit", true), log("tutor_shot", "(0, 0)")]
+        );
+
+        // Alice can't shoot again before confirming the tutor's shot
+        handle(&mut deps, mock_env("alice", &[]), shoot(2, 2)).unwrap_err();
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(0, 0).into(),
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let res = handle(&mut deps, mock_env("alice", &[]), shoot(2, 2)).unwrap();
+        assert_eq!(
+            &res.log[..2],
+            &[log("hit", false), log("tutor_shot", "(1, 0)")]
+        );
+
+        let mut tutor = credentials(TUTOR);
+        tutor.password = Password::default();
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(2, 2).into(),
+            credentials: tutor,
+        };
+        match handle(&mut deps, mock_env("eve", &[]), msg) {
+            Err(StdError::Unauthorized { .. }) => {}
+            other => panic!("Expected unauthorized, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_placement_violations() {
+        let deps = mock_dependencies(20, &[]);
+        let misplaced = Pasture::new(
+            vec![
+                Herd::new(0, 0, 2, Orientation::Horizontal),
+                Herd::new(1, 0, 3, Orientation::Vertical),
+                Herd::new(8, 8, 4, Orientation::Horizontal),
+                Herd::new(0, 8, 5, Orientation::Horizontal),
+            ],
+            vec![],
+        );
+        let msg = QueryMsg::ValidatePasture {
+            pasture: misplaced,
+            preset: None,
+        };
+        let violations: Vec<PlacementViolation> = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(
+            violations,
+            vec![
+                PlacementViolation::OutOfBounds {
+                    herd: 2,
+                    coords: Coords::new(8, 8),
+                },
+                PlacementViolation::TooFewHerds {
+                    length: 3,
+                    expected: 2,
+                    found: 1,
+                },
+                PlacementViolation::Overlap {
+                    first: 0,
+                    first_start: Coords::new(0, 0),
+                    first_end: Coords::new(1, 0),
+                    second: 1,
+                    second_start: Coords::new(1, 0),
+                    second_end: Coords::new(1, 2),
+                },
+            ]
+        );
+
+        let msg = QueryMsg::ValidatePasture {
+            pasture: pasture(),
+            preset: None,
+        };
+        let violations: Vec<PlacementViolation> = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert!(violations.is_empty());
+    }
+
+    #[test]
+    fn test_game_results() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+
+        let mut transcript_log = log("transcript", "");
+        let sheep: Vec<(u8, u8)> = [(2, 0), (3, 2), (3, 4), (4, 6), (5, 8)]
+            .iter()
+            .flat_map(|&(length, y)| (0..length).map(move |x| (x, y)))
+            .collect();
+        for (&(x, y), miss) in sheep.iter().zip((0..10).cycle()) {
+            let msg = HandleMsg::Shoot {
+                coords: Coords::new(x, y).into(),
+                comment: None,
+                credentials: credentials("alice"),
+            };
+            handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+            let msg = HandleMsg::Confirm {
+                coords: Coords::new(x, y).into(),
+                credentials: credentials("bob"),
+            };
+            let res = handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+            if (x, y) == (4, 8) {
+                assert_eq!(res.log[0], log("winner", "alice"));
+                let stats: GameStats = from_slice(res.log[1].value.as_bytes()).unwrap();
+                assert_eq!(stats.duration, Some(0));
+                assert_eq!((stats.winner.shots, stats.winner.hits), (17, 17));
+                assert_eq!(stats.winner.accuracy, 10_000);
+                assert_eq!((stats.loser.shots, stats.loser.hits), (16, 0));
+                transcript_log = res.log[2].clone();
+                break;
+            }
+
+            // Bob keeps missing
+            let miss = Coords::new(miss, 9);
+            let msg = HandleMsg::Shoot {
+                coords: miss.into(),
+                comment: None,
+                credentials: credentials("bob"),
+            };
+            handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+            let msg = HandleMsg::Confirm {
+                coords: miss.into(),
+                credentials: credentials("alice"),
+            };
+            handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        }
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(9, 9).into(),
+            comment: None,
+            credentials: credentials("bob"),
+        };
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap_err();
+
+        let head_to_head = |a: &str, b: &str| -> HeadToHead {
+            let msg = QueryMsg::HeadToHead {
+                a: HumanAddr::from(a),
+                b: HumanAddr::from(b),
+            };
+            from_binary(&query(&deps, msg).unwrap()).unwrap()
+        };
+        assert_eq!(
+            head_to_head("alice", "bob"),
+            HeadToHead { wins: 1, losses: 0 }
+        );
+        assert_eq!(
+            head_to_head("bob", "alice"),
+            HeadToHead { wins: 0, losses: 1 }
+        );
+
+        let msg = QueryMsg::Archive {
+            start_after: None,
+            limit: None,
+        };
+        let archive: Vec<ArchivedGame> = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive[0].winner.username, "alice");
+        assert_eq!(archive[0].loser.pasture.herds(), pasture().herds());
+        assert!(archive[0].loser.pasture.is_cleared());
+
+        let msg = QueryMsg::Transcript {
+            game: "pasture party".parse().unwrap(),
+        };
+        let transcript: TranscriptResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(transcript.index, 0);
+        assert_eq!(transcript.game, archive[0]);
+        assert_eq!(
+            transcript_log,
+            log("transcript", transcript.digest.to_base64())
+        );
+        let mut hasher = Sha256::new();
+        hasher.update(to_vec(&archive[0]).unwrap());
+        assert_eq!(transcript.digest.as_slice(), hasher.finalize().as_slice());
+
+        let msg = QueryMsg::Transcript {
+            game: "pasture picnic".parse().unwrap(),
+        };
+        query(&deps, msg).unwrap_err();
+    }
+
+    #[test]
+    fn test_action_cooldown() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg {
+            action_cooldown: None,
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("admin", &[]), msg).unwrap();
+        let msg = NewGame::default().msg();
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        for username in &["alice", "bob"] {
+            let msg = HandleMsg::Join {
+                pasture: pasture(),
+                credentials: credentials(username),
+            };
+            handle(&mut deps, mock_env(*username, &[]), msg).unwrap();
+        }
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(9, 9).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(9, 9).into(),
+            credentials: credentials("bob"),
+        };
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+
+        // Bob already acted in this block
+        let shoot = HandleMsg::Shoot {
+            coords: Coords::new(9, 9).into(),
+            comment: None,
+            credentials: credentials("bob"),
+        };
+        handle(&mut deps, mock_env("bob", &[]), shoot.clone()).unwrap_err();
+        let mut env = mock_env("bob", &[]);
+        env.block.height += 1;
+        handle(&mut deps, env, shoot).unwrap();
+    }
+
+    #[test]
+    fn test_mailbox_notified() {
+        let mut deps = mock_dependencies(20, &[]);
+        let mailbox = Callback {
+            contract_addr: HumanAddr::from("mailbox"),
+            code_hash: "mailbox code hash".to_string(),
+        };
+        let msg = InitMsg {
+            mailbox: Some(mailbox.clone()),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("admin", &[]), msg).unwrap();
+        let msg = NewGame::default().msg();
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let mut res = HandleResponse::default();
+        for username in &["alice", "bob"] {
+            let msg = HandleMsg::Join {
+                pasture: pasture(),
+                credentials: credentials(username),
+            };
+            res = handle(&mut deps, mock_env(*username, &[]), msg).unwrap();
+        }
+        let your_turn = |recipient: &str, turn| {
+            mailbox
+                .notify(&MailboxMsg::YourTurn {
+                    recipient: HumanAddr::from(recipient),
+                    game: "pasture party".to_string(),
+                    turn,
+                })
+                .unwrap()
+        };
+        assert_eq!(res.messages, vec![your_turn("alice", 0)]);
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(9, 9).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(9, 9).into(),
+            credentials: credentials("bob"),
+        };
+        let res = handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+        assert_eq!(res.messages, vec![your_turn("bob", 1)]);
+    }
+
+    #[test]
+    fn test_lobby_deposit() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg {
+            lobby_deposit: Some(coin(10, "uscrt")),
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("admin", &[]), msg).unwrap();
+        let new_game = |name: &str, lifetime| {
+            NewGame {
+                name: name.parse().unwrap(),
+                lifetime,
+                ..NewGame::default()
+            }
+            .msg()
+        };
+        let refund = |owner: &str| {
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: mock_env("anyone", &[]).contract.address,
+                to_address: HumanAddr::from(owner),
+                amount: coins(10, "uscrt"),
+            })
+        };
+
+        // The deposit is refunded once the game starts
+        let msg = new_game("pasture party", None);
+        handle(&mut deps, mock_env("alice", &[]), msg.clone()).unwrap_err();
+        let env = mock_env("alice", &coins(10, "uscrt"));
+        handle(&mut deps, env, msg).unwrap();
+        let mut res = HandleResponse::default();
+        for username in &["alice", "bob"] {
+            let msg = HandleMsg::Join {
+                pasture: pasture(),
+                credentials: credentials(username),
+            };
+            res = handle(&mut deps, mock_env(*username, &[]), msg).unwrap();
+        }
+        assert_eq!(res.messages, vec![refund("alice")]);
+
+        // Or when its organizer cancels it
+        let env = mock_env("carol", &coins(10, "uscrt"));
+        handle(&mut deps, env, new_game("cancelled", None)).unwrap();
+        let cancel = |name: &str| HandleMsg::CancelGame {
+            name: name.parse().unwrap(),
+        };
+        match handle(&mut deps, mock_env("eve", &[]), cancel("cancelled")) {
+            Err(StdError::Unauthorized { .. }) => {}
+            other => panic!("Expected unauthorized, got {:?}", other),
+        }
+        let res = handle(&mut deps, mock_env("carol", &[]), cancel("cancelled")).unwrap();
+        assert_eq!(res.messages, vec![refund("carol")]);
+        assert!(Game::may_load(&deps.storage, "cancelled".to_string())
+            .unwrap()
+            .is_none());
+        let msg = QueryMsg::ListGames {
+            start_after: None,
+            limit: None,
+            status_filter: None,
+        };
+        let list: ListGamesResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert!(list.games.iter().all(|game| game.name != "cancelled"));
+
+        // And forfeited to the treasury when it expires unjoined
+        let env = mock_env("dave", &coins(10, "uscrt"));
+        handle(&mut deps, env, new_game("abandoned", Some(5))).unwrap();
+        let collect = HandleMsg::CollectDeposit {
+            name: "abandoned".parse().unwrap(),
+        };
+        handle(&mut deps, mock_env("eve", &[]), collect.clone()).unwrap_err();
+        let mut env = mock_env("dave", &[]);
+        env.block.height += 5;
+        handle(&mut deps, env.clone(), cancel("abandoned")).unwrap_err();
+        env.message.sender = HumanAddr::from("eve");
+        handle(&mut deps, env.clone(), collect.clone()).unwrap();
+        handle(&mut deps, env, collect).unwrap_err();
+
+        let msg = QueryMsg::Sponsors {
+            start_after: None,
+            limit: None,
+        };
+        let res: SponsorsResponse = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert!(res.balance.is_empty());
+        assert_eq!(res.forfeited, coins(10, "uscrt"));
+        assert!(res.sponsorships.is_empty());
+    }
+
+    fn wagered_game(name: &str, amount: u128) -> HandleMsg {
+        NewGame {
+            name: name.parse().unwrap(),
+            rules: GameRules {
+                victory: VictoryCondition::FirstToHits { hits: 1 },
+                ..GameRules::default()
+            },
+            lifetime: Some(5),
+            wager: Some(coin(amount, "uscrt")),
+            ..NewGame::default()
+        }
+        .msg()
+    }
+
+    fn join_wagered(name: &str, username: &str) -> HandleMsg {
+        HandleMsg::Join {
+            pasture: pasture(),
+            credentials: Credentials {
+                game: name.parse().unwrap(),
+                ..credentials(username)
+            },
+        }
+    }
+
+    fn bank_send(to: &str, amount: u128) -> CosmosMsg {
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: mock_env("anyone", &[]).contract.address,
+            to_address: HumanAddr::from(to),
+            amount: coins(amount, "uscrt"),
+        })
+    }
+
+    #[test]
+    fn test_wager() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        match handle(&mut deps, mock_env("alice", &[]), wagered_game("free", 0)) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "A wager has to stake something");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        let msg = wagered_game("pasture party", 5);
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+
+        // Both players stake the wager when joining
+        let msg = join_wagered("pasture party", "alice");
+        match handle(
+            &mut deps,
+            mock_env("alice", &coins(4, "uscrt")),
+            msg.clone(),
+        ) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Joining this game requires a stake of 5uscrt");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("alice", &coins(5, "uscrt")), msg).unwrap();
+        let msg = join_wagered("pasture party", "bob");
+        handle(&mut deps, mock_env("bob", &[]), msg.clone()).unwrap_err();
+        handle(&mut deps, mock_env("bob", &coins(5, "uscrt")), msg).unwrap();
+
+        // A third player can't stake anything once both seats are taken
+        let msg = join_wagered("pasture party", "carol");
+        match handle(&mut deps, mock_env("carol", &coins(5, "uscrt")), msg) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Game already full!");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+
+        let msg = QueryMsg::GameStatus {
+            game: "pasture party".parse().unwrap(),
+        };
+        let status: GameStatus = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(status.wager, Some(coin(5, "uscrt")));
+
+        // The winner takes the pot
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(0, 0).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(0, 0).into(),
+            credentials: credentials("bob"),
+        };
+        let res = handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+        assert_eq!(res.log[0], log("winner", "alice"));
+        assert_eq!(res.messages, vec![bank_send("alice", 10)]);
+        let msg = HandleMsg::RefundStakes {
+            name: "pasture party".parse().unwrap(),
+        };
+        let mut env = mock_env("eve", &[]);
+        env.block.height += 5;
+        match handle(&mut deps, env, msg) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "The game was already decided");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_refund_stakes() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+
+        // Stakes in a lobby nobody else joined go back once it expires
+        handle(&mut deps, mock_env("carol", &[]), wagered_game("lonely", 5)).unwrap();
+        let msg = join_wagered("lonely", "carol");
+        handle(&mut deps, mock_env("carol", &coins(5, "uscrt")), msg).unwrap();
+        let refund_stakes = HandleMsg::RefundStakes {
+            name: "lonely".parse().unwrap(),
+        };
+        let mut env = mock_env("eve", &[]);
+        handle(&mut deps, env.clone(), refund_stakes.clone()).unwrap_err();
+        env.block.height += 5;
+        let res = handle(&mut deps, env.clone(), refund_stakes.clone()).unwrap();
+        assert_eq!(res.messages, vec![bank_send("carol", 5)]);
+        handle(&mut deps, env, refund_stakes).unwrap_err();
+
+        // A game that started but expired undecided is forfeited by the player on turn
+        handle(
+            &mut deps,
+            mock_env("carol", &[]),
+            wagered_game("stalled", 5),
+        )
+        .unwrap();
+        for username in &["carol", "dave"] {
+            let msg = join_wagered("stalled", username);
+            handle(&mut deps, mock_env(*username, &coins(5, "uscrt")), msg).unwrap();
+        }
+        let refund_stakes = HandleMsg::RefundStakes {
+            name: "stalled".parse().unwrap(),
+        };
+        let mut env = mock_env("eve", &[]);
+        match handle(&mut deps, env.clone(), refund_stakes.clone()) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "The game hasn't expired yet");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        env.block.height += 5;
+        let res = handle(&mut deps, env.clone(), refund_stakes.clone()).unwrap();
+        assert_eq!(res.log[0], log("winner", "dave"));
+        assert_eq!(res.messages, vec![bank_send("dave", 10)]);
+        match handle(&mut deps, env, refund_stakes) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "The game was already decided");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unconfirmed_shot_forfeits_stakes() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        handle(&mut deps, mock_env("carol", &[]), wagered_game("sulky", 5)).unwrap();
+        for username in &["carol", "dave"] {
+            let msg = join_wagered("sulky", username);
+            handle(&mut deps, mock_env(*username, &coins(5, "uscrt")), msg).unwrap();
+        }
+
+        // Dave would lose to the pending shot, so it never gets confirmed
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(0, 0).into(),
+            comment: None,
+            credentials: Credentials {
+                game: "sulky".parse().unwrap(),
+                ..credentials("carol")
+            },
+        };
+        handle(&mut deps, mock_env("carol", &[]), msg).unwrap();
+        let msg = HandleMsg::RefundStakes {
+            name: "sulky".parse().unwrap(),
+        };
+        let mut env = mock_env("eve", &[]);
+        env.block.height += 5;
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(res.log[0], log("winner", "carol"));
+        assert_eq!(res.messages, vec![bank_send("carol", 10)]);
+    }
+
+    #[test]
+    fn test_deprecations() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg {
+            deprecations: vec![Deprecation {
+                message: "emote".to_string(),
+                replacement: "shoot".to_string(),
+                sunset: 12_400,
+            }],
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("admin", &[]), msg).unwrap();
+        let msg = NewGame::default().msg();
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        for username in &["alice", "bob"] {
+            let msg = HandleMsg::Join {
+                pasture: pasture(),
+                credentials: credentials(username),
+            };
+            let res = handle(&mut deps, mock_env(*username, &[]), msg).unwrap();
+            assert!(res
+                .log
+                .iter()
+                .all(|attribute| attribute.key != "deprecated"));
+        }
+        let emote = HandleMsg::Emote {
+            emote: Emote::Wave,
+            credentials: credentials("alice"),
+        };
+
+        // Deprecated messages keep working until their sunset, but say what replaces them
+        let res = handle(&mut deps, mock_env("alice", &[]), emote.clone()).unwrap();
+        assert_eq!(
+            res.log[res.log.len() - 2..],
+            [log("deprecated", "shoot"), log("sunset", 12_400)]
+        );
+
+        let mut env = mock_env("alice", &[]);
+        env.block.height = 12_400;
+        match handle(&mut deps, env, emote) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(
+                    msg,
+                    "The emote message was retired at height 12400. Send shoot instead"
+                );
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_banned_words() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg {
+            banned_words: vec!["Wolf".to_string()],
+            ..init_msg()
+        };
+        init(&mut deps, mock_env("admin", &[]), msg).unwrap();
+        let new_game = |name: &str| {
+            NewGame {
+                name: name.parse().unwrap(),
+                ..NewGame::default()
+            }
+            .msg()
+        };
+
+        match handle(&mut deps, mock_env("alice", &[]), new_game("WOLF pack")) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "This text contains a word that isn't allowed");
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("alice", &[]), new_game("pasture party")).unwrap();
+
+        let join = |username: &str| HandleMsg::Join {
+            pasture: pasture(),
+            credentials: credentials(username),
+        };
+        handle(&mut deps, mock_env("wolfgang", &[]), join("wolfgang")).unwrap_err();
+        handle(&mut deps, mock_env("alice", &[]), join("alice")).unwrap();
+    }
+
+    #[test]
+    fn test_version() {
+        let deps = mock_dependencies(20, &[]);
+        let res: VersionResponse =
+            from_binary(&query(&deps, QueryMsg::Version {}).unwrap()).unwrap();
+        assert_eq!(res.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(res.state_version, STATE_VERSION);
+        assert_eq!(res.rules_versions.last(), Some(&RULES_VERSION));
+    }
+
+    #[test]
+    fn test_game_status() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+
+        let turns = [
+            ("alice", "bob", 0, Some("Here we go")),
+            ("bob", "alice", 9, Some("Anyone home?")),
+            ("alice", "bob", 1, None),
+        ];
+        for &(shooter, target, x, comment) in &turns {
+            let msg = HandleMsg::Shoot {
+                coords: Coords::new(x, 0).into(),
+                comment: comment.map(|comment| comment.parse().unwrap()),
+                credentials: credentials(shooter),
+            };
+            handle(&mut deps, mock_env(shooter, &[]), msg).unwrap();
+            let msg = HandleMsg::Confirm {
+                coords: Coords::new(x, 0).into(),
+                credentials: credentials(target),
+            };
+            handle(&mut deps, mock_env(target, &[]), msg).unwrap();
+        }
+
+        let msg = QueryMsg::GameStatus {
+            game: "pasture party".parse().unwrap(),
+        };
+        let status: GameStatus = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(status.turns, 3);
+        assert_eq!(status.winner, None);
+        assert_eq!(status.sides[0].sunk_herds, 0);
+        assert_eq!(status.sides[1].sunk_herds, 1);
+        let remaining: Vec<_> = status.sides[1]
+            .remaining_fleet
+            .iter()
+            .map(|entry| (entry.length, entry.count))
+            .collect();
+        assert_eq!(remaining, vec![(3, 2), (4, 1), (5, 1)]);
+        // Comments last until the player's next shot
+        assert_eq!(status.sides[0].comment, None);
+        assert_eq!(status.sides[1].comment, Some("Anyone home?".to_string()));
+    }
+
+    #[test]
+    fn test_sudden_death() {
+        let start = |sudden_death| {
+            let mut deps = mock_dependencies(20, &[]);
+            let msg = NewGame {
+                rules: GameRules {
+                    sudden_death: Some(sudden_death),
+                    ..GameRules::default()
+                },
+                ..NewGame::default()
+            }
+            .msg();
+            start_game_with(&mut deps, msg);
+            deps
+        };
+        let shoot = |username: &str, (x, y)| HandleMsg::Shoot {
+            coords: Coords::new(x, y).into(),
+            comment: None,
+            credentials: credentials(username),
+        };
+        let confirm = |username: &str, (x, y)| HandleMsg::Confirm {
+            coords: Coords::new(x, y).into(),
+            credentials: credentials(username),
+        };
+        // Every sheep but those of the last herd, at the bottom of the pasture
+        let sheep: Vec<(u8, u8)> = [(2, 0), (3, 2), (3, 4), (4, 6)]
+            .iter()
+            .flat_map(|&(length, y)| (0..length).map(move |x| (x, y)))
+            .collect();
+
+        // With double shots, bob gets a second shot after bringing alice down to her last herd
+        let mut deps = start(SuddenDeath::DoubleShots);
+        for &coords in &sheep {
+            handle(&mut deps, mock_env("alice", &[]), shoot("alice", coords)).unwrap();
+            handle(&mut deps, mock_env("bob", &[]), confirm("bob", coords)).unwrap();
+            handle(&mut deps, mock_env("bob", &[]), shoot("bob", coords)).unwrap();
+            handle(&mut deps, mock_env("alice", &[]), confirm("alice", coords)).unwrap();
+        }
+        for &(shooter, target) in &[("bob", "alice"), ("alice", "bob"), ("alice", "bob")] {
+            handle(&mut deps, mock_env(shooter, &[]), shoot(shooter, (9, 9))).unwrap();
+            handle(&mut deps, mock_env(target, &[]), confirm(target, (9, 9))).unwrap();
+        }
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice", (9, 9))).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), shoot("bob", (9, 9))).unwrap();
+
+        // Revealing the outer ring sinks the last sheep of bob, after alice shot the others
+        let mut deps = start(SuddenDeath::OuterRing);
+        let mut alice_targets = vec![(1, 8), (2, 8), (3, 8), (4, 8)];
+        alice_targets.extend(sheep.iter().copied());
+        let mut bob_targets = vec![(5, 9), (6, 9), (7, 9), (8, 9)];
+        bob_targets.extend(sheep.iter().copied());
+        let mut res = HandleResponse::default();
+        for (&alice_target, &bob_target) in alice_targets.iter().zip(bob_targets.iter()) {
+            handle(
+                &mut deps,
+                mock_env("alice", &[]),
+                shoot("alice", alice_target),
+            )
+            .unwrap();
+            handle(
+                &mut deps,
+                mock_env("bob", &[]),
+                confirm("bob", alice_target),
+            )
+            .unwrap();
+            handle(&mut deps, mock_env("bob", &[]), shoot("bob", bob_target)).unwrap();
+            res = handle(
+                &mut deps,
+                mock_env("alice", &[]),
+                confirm("alice", bob_target),
+            )
+            .unwrap();
+        }
+        assert_eq!(res.log[0], log("winner", "alice"));
+    }
+
+    #[test]
+    fn test_scoring() {
+        let mut deps = mock_dependencies(20, &[]);
+        let new_game = |rounds| {
+            NewGame {
+                rules: GameRules {
+                    victory: VictoryCondition::PointsAfterRounds { rounds },
+                    ..GameRules::default()
+                },
+                ..NewGame::default()
+            }
+            .msg()
+        };
+        init_contract(&mut deps);
+        handle(&mut deps, mock_env("alice", &[]), new_game(0)).unwrap_err();
+        start_game_with(&mut deps, new_game(1));
+
+        // Both hit once in the only round, so the game goes on
+        let turns = [
+            ("alice", "bob", (0, 0)),
+            ("bob", "alice", (0, 0)),
+            ("alice", "bob", (1, 0)),
+            ("bob", "alice", (9, 9)),
+        ];
+        let mut res = HandleResponse::default();
+        for &(shooter, target, (x, y)) in &turns {
+            let msg = HandleMsg::Shoot {
+                coords: Coords::new(x, y).into(),
+                comment: None,
+                credentials: credentials(shooter),
+            };
+            handle(&mut deps, mock_env(shooter, &[]), msg).unwrap();
+            let msg = HandleMsg::Confirm {
+                coords: Coords::new(x, y).into(),
+                credentials: credentials(target),
+            };
+            res = handle(&mut deps, mock_env(target, &[]), msg).unwrap();
+        }
+
+        // Alice sank a herd in the second round, and leads by points
+        assert_eq!(res.log[0], log("winner", "alice"));
+        let msg = QueryMsg::GameStatus {
+            game: "pasture party".parse().unwrap(),
+        };
+        let status: GameStatus = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(status.winner, Some("alice".to_string()));
+        assert_eq!(status.sides[0].points, Some(5));
+        assert_eq!(status.sides[1].points, Some(1));
+    }
+
+    #[test]
+    fn test_sender_auth() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game_with(
+            &mut deps,
+            NewGame {
+                rules: GameRules {
+                    sender_auth: true,
+                    ..GameRules::default()
+                },
+                ..NewGame::default()
+            }
+            .msg(),
+        );
+        let shoot = || HandleMsg::Shoot {
+            coords: Coords::new(9, 9).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+
+        // Knowing alice's password isn't enough to play for her
+        match handle(&mut deps, mock_env("mallory", &[]), shoot()) {
+            Err(StdError::Unauthorized { .. }) => {}
+            other => panic!("Expected unauthorized, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("alice", &[]), shoot()).unwrap();
+
+        // Queries have no sender, so they still go by the password
+        let msg = QueryMsg::MyPasture {
+            credentials: credentials("alice"),
+        };
+        query(&deps, msg).unwrap();
+    }
+
+    #[test]
+    fn test_passing() {
+        let mut deps = mock_dependencies(20, &[]);
+        let new_game = |victory| {
+            NewGame {
+                rules: GameRules {
+                    passing: true,
+                    victory,
+                    ..GameRules::default()
+                },
+                ..NewGame::default()
+            }
+            .msg()
+        };
+        init_contract(&mut deps);
+        match handle(
+            &mut deps,
+            mock_env("alice", &[]),
+            new_game(VictoryCondition::EliminateAll),
+        ) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+                msg,
+                "Passing is only allowed in games won by hits, points or survival"
+            ),
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        start_game_with(
+            &mut deps,
+            new_game(VictoryCondition::PointsAfterRounds { rounds: 1 }),
+        );
+        let pass = |username: &str| HandleMsg::Pass {
+            credentials: credentials(username),
+        };
+
+        // A round of passes leaves the game tied
+        handle(&mut deps, mock_env("bob", &[]), pass("bob")).unwrap_err();
+        let res = handle(&mut deps, mock_env("alice", &[]), pass("alice")).unwrap();
+        assert_eq!(res.log[0], log("passed", "alice"));
+        handle(&mut deps, mock_env("bob", &[]), pass("bob")).unwrap();
+
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(1, 0).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        handle(&mut deps, mock_env("alice", &[]), pass("alice")).unwrap_err();
+        let msg = HandleMsg::Confirm {
+            coords: Coords::new(1, 0).into(),
+            credentials: credentials("bob"),
+        };
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+
+        // Bob holds fire at the end of the round, and loses on points
+        let res = handle(&mut deps, mock_env("bob", &[]), pass("bob")).unwrap();
+        assert_eq!(res.log[1], log("winner", "alice"));
+    }
+
+    #[test]
+    fn test_limited_ammo() {
+        fn play<S: Storage, A: Api, Q: Querier>(
+            deps: &mut Extern<S, A, Q>,
+            shots: &[(&str, &str, (u8, u8))],
+        ) -> HandleResponse {
+            let mut res = HandleResponse::default();
+            for &(shooter, target, (x, y)) in shots {
+                let msg = HandleMsg::Shoot {
+                    coords: Coords::new(x, y).into(),
+                    comment: None,
+                    credentials: credentials(shooter),
+                };
+                handle(deps, mock_env(shooter, &[]), msg).unwrap();
+                let msg = HandleMsg::Confirm {
+                    coords: Coords::new(x, y).into(),
+                    credentials: credentials(target),
+                };
+                res = handle(deps, mock_env(target, &[]), msg).unwrap();
+            }
+            res
+        }
+        let new_game = |victory, ammo| {
+            NewGame {
+                rules: GameRules {
+                    ammo: Some(ammo),
+                    victory,
+                    ..GameRules::default()
+                },
+                ..NewGame::default()
+            }
+            .msg()
+        };
+
+        // Both players are out of shots long before the last round, and alice leads
+        let mut deps = mock_dependencies(20, &[]);
+        let victory = VictoryCondition::PointsAfterRounds { rounds: 10 };
+        start_game_with(&mut deps, new_game(victory, 1));
+        let res = play(
+            &mut deps,
+            &[("alice", "bob", (1, 0)), ("bob", "alice", (9, 9))],
+        );
+        assert_eq!(res.log[0], log("winner", "alice"));
+        let msg = QueryMsg::GameStatus {
+            game: "pasture party".parse().unwrap(),
+        };
+        let status: GameStatus = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        let ammo: Vec<_> = status.sides.iter().map(|side| side.ammo).collect();
+        assert_eq!(ammo, vec![Some(0), Some(0)]);
+
+        // Tied players who can't shoot anymore don't wait for another round. Bob landed
+        // his hit first, with fewer shots.
+        let mut deps = mock_dependencies(20, &[]);
+        start_game_with(&mut deps, new_game(victory, 2));
+        let res = play(
+            &mut deps,
+            &[
+                ("alice", "bob", (9, 9)),
+                ("bob", "alice", (1, 0)),
+                ("alice", "bob", (1, 0)),
+                ("bob", "alice", (9, 9)),
+            ],
+        );
+        assert_eq!(res.log[0], log("winner", "bob"));
+
+        // Games won by clearing pastures go to whoever hit more sheep
+        let mut deps = mock_dependencies(20, &[]);
+        start_game_with(&mut deps, new_game(VictoryCondition::EliminateAll, 1));
+        let res = play(
+            &mut deps,
+            &[("alice", "bob", (9, 9)), ("bob", "alice", (0, 2))],
+        );
+        assert_eq!(res.log[0], log("winner", "bob"));
+        let msg = HandleMsg::Shoot {
+            coords: Coords::new(0, 0).into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap_err();
+    }
+
+    #[test]
+    fn test_distinct_players() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let msg = NewGame {
+            rules: GameRules {
+                distinct_players: true,
+                ..GameRules::default()
+            },
+            ..NewGame::default()
+        }
+        .msg();
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let join = |credentials| HandleMsg::Join {
+            pasture: pasture(),
+            credentials,
+        };
+        handle(
+            &mut deps,
+            mock_env("alice", &[]),
+            join(credentials("alice")),
+        )
+        .unwrap();
+
+        let reused_password = Credentials {
+            password: credentials("alice").password,
+            ..credentials("bob")
+        };
+        match handle(&mut deps, mock_env("bob", &[]), join(reused_password)) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(
+                    msg,
+                    "Both seats of this game can't be taken by the same player"
+                );
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        handle(&mut deps, mock_env("alice", &[]), join(credentials("bob"))).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), join(credentials("bob"))).unwrap();
+    }
+
+    #[test]
+    fn test_committed_shots() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = NewGame {
+            rules: GameRules {
+                committed_shots: true,
+                ..GameRules::default()
+            },
+            ..NewGame::default()
+        }
+        .msg();
+        start_game_with(&mut deps, msg);
+        let coords = Coords::new(0, 0);
+        let env = |height| {
+            let mut env = mock_env("alice", &[]);
+            env.block.height = height;
+            env
+        };
+        let reveal = |salt: &str| HandleMsg::RevealShot {
+            coords: coords.into(),
+            salt: salt.to_string(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+
+        let msg = HandleMsg::Shoot {
+            coords: coords.into(),
+            comment: None,
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, env(12_345), msg).unwrap_err();
+        let msg = HandleMsg::CommitShot {
+            hash: Binary(shot_commitment(coords, "pepper")),
+            credentials: credentials("alice"),
+        };
+        handle(&mut deps, env(12_345), msg).unwrap();
+
+        // The shot can't be revealed in the block it was committed in, nor with another salt
+        handle(&mut deps, env(12_345), reveal("pepper")).unwrap_err();
+        handle(&mut deps, env(12_346), reveal("salt")).unwrap_err();
+        handle(&mut deps, env(12_346), reveal("pepper")).unwrap();
+
+        let msg = HandleMsg::Confirm {
+            coords: coords.into(),
+            credentials: credentials("bob"),
+        };
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_opening_salvo() {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = NewGame {
+            rules: GameRules {
+                opening_salvo: true,
+                ..GameRules::default()
+            },
+            ..NewGame::default()
+        }
+        .msg();
+        start_game_with(&mut deps, msg);
+        let coords = Coords::new(0, 0);
+        let commit = |username: &str| HandleMsg::CommitOpening {
+            hash: Binary(shot_commitment(coords, "pepper")),
+            credentials: credentials(username),
+        };
+        let reveal = |username: &str| HandleMsg::RevealOpening {
+            coords: coords.into(),
+            salt: "pepper".to_string(),
+            credentials: credentials(username),
+        };
+
+        // A shot revealed without a commitment could be picked after seeing the other one
+        handle(&mut deps, mock_env("alice", &[]), commit("alice")).unwrap();
+        match handle(&mut deps, mock_env("bob", &[]), reveal("bob")) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(
+                    msg,
+                    "Both players must commit to an opening shot before revealing"
+                );
+            }
+            other => panic!("Expected a generic error, got {:?}", other),
+        }
+        let mut game = Game::load(&deps.storage, "pasture party".to_string())
+            .unwrap()
+            .full()
+            .unwrap();
+        game.reveal_opening(&credentials("bob"), coords, "pepper", 12_345)
+            .unwrap_err();
+
+        handle(&mut deps, mock_env("bob", &[]), commit("bob")).unwrap();
+        for username in &["alice", "bob"] {
+            handle(&mut deps, mock_env(*username, &[]), reveal(username)).unwrap();
+        }
+        let game = Game::load(&deps.storage, "pasture party".to_string()).unwrap();
+        assert!(!game.in_opening());
+        assert_eq!(game.players()[0].pasture().shots(), &[coords]);
+    }
+
+    #[test]
+    fn test_reinforcements() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let new_game = |victory| {
+            NewGame {
+                rules: GameRules {
+                    reinforcements: true,
+                    victory,
+                    ..GameRules::default()
+                },
+                ..NewGame::default()
+            }
+            .msg()
+        };
+        let msg = new_game(VictoryCondition::FirstToHits { hits: 3 });
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap_err();
+        let msg = new_game(VictoryCondition::EliminateAll);
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+
+        // Players start with 3 of their 5 herds
+        let join = |username: &str, pasture| HandleMsg::Join {
+            pasture,
+            credentials: credentials(username),
+        };
+        let msg = join("alice", pasture());
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap_err();
+        let partial = || {
+            Pasture::new(
+                vec![
+                    Herd::new(0, 0, 2, Orientation::Horizontal),
+                    Herd::new(0, 2, 3, Orientation::Horizontal),
+                    Herd::new(0, 4, 3, Orientation::Horizontal),
+                ],
+                vec![],
+            )
+        };
+        for username in &["alice", "bob"] {
+            handle(
+                &mut deps,
+                mock_env(*username, &[]),
+                join(username, partial()),
+            )
+            .unwrap();
+        }
+
+        let shoot = |username: &str, x| HandleMsg::Shoot {
+            coords: Coords::new(x, 9).into(),
+            comment: None,
+            credentials: credentials(username),
+        };
+        let confirm = |username: &str, x| HandleMsg::Confirm {
+            coords: Coords::new(x, 9).into(),
+            credentials: credentials(username),
+        };
+        let place = |username: &str, herd| HandleMsg::PlaceHerd {
+            herd,
+            credentials: credentials(username),
+        };
+        for turn in 0..5 {
+            let (shooter, target) = if turn % 2 == 0 {
+                ("alice", "bob")
+            } else {
+                ("bob", "alice")
+            };
+            let msg = place(shooter, Herd::new(0, 6, 4, Orientation::Horizontal));
+            handle(&mut deps, mock_env(shooter, &[]), msg).unwrap_err();
+            handle(&mut deps, mock_env(shooter, &[]), shoot(shooter, turn)).unwrap();
+            handle(&mut deps, mock_env(target, &[]), confirm(target, turn)).unwrap();
+        }
+
+        // After 5 turns, bob has to place a reinforcement before shooting
+        handle(&mut deps, mock_env("bob", &[]), shoot("bob", 5)).unwrap_err();
+        // It can't cover cells alice already shot, nor break the fleet
+        let msg = place("bob", Herd::new(0, 9, 4, Orientation::Horizontal));
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap_err();
+        let msg = place("bob", Herd::new(0, 6, 3, Orientation::Horizontal));
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap_err();
+        let msg = place("bob", Herd::new(0, 6, 4, Orientation::Horizontal));
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+        let msg = place("bob", Herd::new(0, 8, 5, Orientation::Horizontal));
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap_err();
+        handle(&mut deps, mock_env("bob", &[]), shoot("bob", 5)).unwrap();
+        handle(&mut deps, mock_env("alice", &[]), confirm("alice", 5)).unwrap();
+
+        // Alice's reinforcement is due on her next turn
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice", 6)).unwrap_err();
+        let msg = place("alice", Herd::new(0, 6, 4, Orientation::Horizontal));
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        handle(&mut deps, mock_env("alice", &[]), shoot("alice", 6)).unwrap();
+    }
+
+    #[test]
+    fn test_first_to_hits() {
+        let mut deps = mock_dependencies(20, &[]);
+        let new_game = |hits| {
+            NewGame {
+                rules: GameRules {
+                    victory: VictoryCondition::FirstToHits { hits },
+                    ..GameRules::default()
+                },
+                ..NewGame::default()
+            }
+            .msg()
+        };
+        init_contract(&mut deps);
+        handle(&mut deps, mock_env("alice", &[]), new_game(0)).unwrap_err();
+        start_game_with(&mut deps, new_game(2));
+
+        let turns = [
+            ("alice", "bob", 0),
+            ("bob", "alice", 0),
+            ("alice", "bob", 2),
+        ];
+        let mut res = HandleResponse::default();
+        for &(shooter, target, x) in &turns {
+            let msg = HandleMsg::Shoot {
+                coords: Coords::new(x, 2).into(),
+                comment: None,
+                credentials: credentials(shooter),
+            };
+            handle(&mut deps, mock_env(shooter, &[]), msg).unwrap();
+            let msg = HandleMsg::Confirm {
+                coords: Coords::new(x, 2).into(),
+                credentials: credentials(target),
+            };
+            res = handle(&mut deps, mock_env(target, &[]), msg).unwrap();
+        }
+
+        // Alice hit two sheep without sinking a herd
+        assert_eq!(res.log[0], log("winner", "alice"));
+    }
+
+    #[test]
+    fn test_simulate() {
+        let mut deps = mock_dependencies(20, &[]);
+        start_game(&mut deps);
+        let simulate = |username: &str| QueryMsg::Simulate {
+            msg: Box::new(HandleMsg::Shoot {
+                coords: Coords::new(0, 0).into(),
+                comment: None,
+                credentials: credentials(username),
+            }),
+            sender: HumanAddr::from(username),
+            sent_funds: vec![],
+            height: 12_345,
+        };
+
+        let res: SimulateResponse = from_binary(&query(&deps, simulate("alice")).unwrap()).unwrap();
+        assert_eq!(res.error, None);
+        let res: SimulateResponse = from_binary(&query(&deps, simulate("bob")).unwrap()).unwrap();
+        assert_eq!(
+            res.error,
+            Some("Generic error: It's not your turn".to_string())
+        );
+
+        // The simulated shot was never fired
+        let msg = QueryMsg::TurnInfo {
+            game: "pasture party".parse().unwrap(),
+        };
+        let turn_info: TurnInfo = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert!(!turn_info.shot_pending);
+    }
+
+    #[test]
+    fn test_locale() {
+        let mut deps = mock_dependencies(20, &[]);
+        init_contract(&mut deps);
+        let new_game = |locale: &str| {
+            NewGame {
+                locale: Some(locale.to_string()),
+                ..NewGame::default()
+            }
+            .msg()
+        };
+        handle(&mut deps, mock_env("alice", &[]), new_game("klingon")).unwrap_err();
+        handle(&mut deps, mock_env("alice", &[]), new_game("fr")).unwrap();
+
+        let msg = QueryMsg::GameStatus {
+            game: "pasture party".parse().unwrap(),
+        };
+        let status: GameStatus = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(status.locale, Some("fr".to_string()));
+    }
 }
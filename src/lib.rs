@@ -1,6 +1,17 @@
+#[cfg(feature = "analysis")]
+pub mod analysis;
+pub mod archive;
+pub mod bounded;
 pub mod contract;
+pub mod lobby;
 pub mod msg;
+pub mod practice;
+pub mod presets;
+pub mod records;
+pub mod rules;
 pub mod state;
+pub mod storage;
+pub mod treasury;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
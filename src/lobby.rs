@@ -0,0 +1,39 @@
+use std::ops::Range;
+
+use cosmwasm_std::{StdResult, Storage};
+
+use crate::storage::{self, Pagination};
+
+/// Add a new game to the list clients discover games from
+pub fn list_game<S: Storage>(storage: &mut S, name: &str) -> StdResult<()> {
+    let size = list_size(storage)?;
+    storage::game_list(storage).save(&size.to_be_bytes(), &name.to_string())?;
+    storage::game_list_index(storage).save(name.as_bytes(), &size)?;
+    storage::game_list_size(storage).save(&(size + 1))
+}
+
+/// Take a removed game off the list. Games that were never listed are left alone.
+pub fn unlist_game<S: Storage>(storage: &mut S, name: &str) -> StdResult<()> {
+    if let Some(index) = storage::game_list_index_read(storage).may_load(name.as_bytes())? {
+        storage::game_list(storage).remove(&index.to_be_bytes());
+        storage::game_list_index(storage).remove(name.as_bytes());
+    }
+
+    Ok(())
+}
+
+/// The indexes on a page of the list
+pub fn list_page<S: Storage>(storage: &S, page: Pagination) -> StdResult<Range<u32>> {
+    Ok(page.range(list_size(storage)?))
+}
+
+/// The name of the game listed under this index, or `None` if it was removed
+pub fn listed_game<S: Storage>(storage: &S, index: u32) -> StdResult<Option<String>> {
+    storage::game_list_read(storage).may_load(&index.to_be_bytes())
+}
+
+fn list_size<S: Storage>(storage: &S) -> StdResult<u32> {
+    storage::game_list_size_read(storage)
+        .may_load()
+        .map(Option::unwrap_or_default)
+}
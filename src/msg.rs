@@ -1,50 +1,581 @@
+use cosmwasm_std::{
+    to_binary, Binary, Coin, CosmosMsg, HumanAddr, LogAttribute, StdResult, WasmMsg,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::{Coords, Pasture};
+use crate::archive::ArchivedGame;
+use crate::bounded::{BoundedCoords, Comment, GameName, Password, TemplateName, Username};
+use crate::presets::{BoardConfig, FleetEntry};
+use crate::rules::GameRules;
+use crate::state::{Coords, Herd, Pasture, Phase, Template};
+use crate::treasury::Sponsorship;
 
-/// Initialization doesn't take any parameters
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InitMsg {}
+pub struct InitMsg {
+    /// The amount of blocks games last by default before they expire
+    pub game_lifetime: Option<u64>,
+    /// The amount of blocks a player has to wait between two actions in a game.
+    /// Defaults to 1, allowing one action per block. 0 disables the limit.
+    pub action_cooldown: Option<u64>,
+    /// A mailbox contract to tell players when it's their turn
+    pub mailbox: Option<Callback>,
+    /// A deposit that opening a lobby requires, which is refunded once the game starts
+    pub lobby_deposit: Option<Coin>,
+    /// Words that game names, template names, usernames and comments can't contain,
+    /// in any case
+    #[serde(default)]
+    pub banned_words: Vec<String>,
+    /// The only contracts games may send callbacks to. Games can name any contract if this
+    /// isn't set.
+    pub allowed_callbacks: Option<Vec<HumanAddr>>,
+    /// Messages that are being phased out, and the heights they stop working at
+    #[serde(default)]
+    pub deprecations: Vec<Deprecation>,
+}
+
+/// A message that still works until its sunset height, but should be replaced
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Deprecation {
+    /// The name of the deprecated message, like "shoot"
+    pub message: String,
+    /// The name of the message to send instead
+    pub replacement: String,
+    /// The block height from which the deprecated message is refused
+    pub sunset: u64,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
     /// Start a game
-    NewGame { name: String },
+    NewGame {
+        name: GameName,
+        /// The name of the board preset to play with. Defaults to "classic".
+        preset: Option<String>,
+        /// Play on a pasture of this size instead of a preset, with a fleet to match
+        #[serde(default)]
+        size: Option<u8>,
+        /// The optional rules to play with. None are used by default.
+        #[serde(default)]
+        rules: GameRules,
+        /// A contract to notify after every resolved turn
+        callback: Option<Callback>,
+        /// The amount of blocks this game lasts, overriding the default
+        lifetime: Option<u64>,
+        /// The language the players are expected to speak, as one of the tags in `LOCALES`
+        #[serde(default)]
+        locale: Option<String>,
+        /// The stake each player has to send along when joining. The winner takes both.
+        #[serde(default)]
+        wager: Option<Coin>,
+    },
+    /// Save settings that games can later be created from.
+    ///
+    /// Anyone can create a new template, but only its organizer can overwrite it.
+    SaveTemplate {
+        name: TemplateName,
+        board: BoardConfig,
+        #[serde(default)]
+        rules: GameRules,
+        lifetime: Option<u64>,
+    },
+    /// Delete a template. Only its organizer can do this.
+    DeleteTemplate { name: TemplateName },
+    /// Start a game with the settings of a template
+    NewGameFromTemplate {
+        template: TemplateName,
+        name: GameName,
+    },
+    /// Cancel a lobby that hasn't started yet, and refund its deposit.
+    /// Only the address that paid the deposit can do this.
+    CancelGame { name: GameName },
+    /// Move the deposit of a lobby that expired before it started to the treasury.
+    /// Anyone can do this.
+    CollectDeposit { name: GameName },
+    /// Settle the stakes of a wagered game that expired undecided. A game that started is
+    /// forfeited by the player who owed it the next action, and its pot goes to their
+    /// opponent. The stakes of a lobby, or of an opening salvo both players got equally far
+    /// through, go back to the players who paid them. Anyone can do this.
+    RefundStakes { name: GameName },
+    /// Start a practice game against a scripted tutor, and join it.
+    ///
+    /// The tutor confirms every shot right away and fires back, so the player only has to
+    /// confirm the tutor's shots.
+    NewPracticeGame {
+        pasture: Pasture,
+        credentials: Credentials,
+    },
     /// Player joins the arena and sets a username and random password.
+    /// Games played for a wager require sending the stake along.
     Join {
         pasture: Pasture,
         credentials: Credentials,
     },
     /// Shoot at enemy pasture
     Shoot {
-        coords: Coords,
+        coords: BoundedCoords,
+        /// A public comment on the shot, shown in the game status until the next shot
+        #[serde(default)]
+        comment: Option<Comment>,
         credentials: Credentials,
     },
     /// confirm the shot made by the previous player
     Confirm {
-        coords: Coords,
+        coords: BoundedCoords,
+        credentials: Credentials,
+    },
+    /// Confirm the pending shot without naming it, in games with strict confirms, where
+    /// it's kept hidden from me
+    ConfirmBlind { credentials: Credentials },
+    /// Pass my turn without shooting, in games that allow it
+    Pass { credentials: Credentials },
+    /// Ask to take back the pending shot, before it is confirmed
+    RequestTakeback { credentials: Credentials },
+    /// Let the opponent take back their pending shot, so they can pick another one
+    GrantTakeback { credentials: Credentials },
+    /// React during my own turn. The emote is only published in the logs of the
+    /// transaction, and nothing about it is stored.
+    Emote {
+        emote: Emote,
+        credentials: Credentials,
+    },
+    /// Consent to let anyone who knows `key` watch both pastures of the game, for example
+    /// on a stream. The key only works once both players consented to the same one.
+    AllowObserver {
+        key: Password,
         credentials: Credentials,
     },
+    /// Withdraw consent to being watched, which stops the observer key from working
+    RevokeObserver { credentials: Credentials },
+    /// Commit to the opening shot, before either player's shot is revealed.
+    ///
+    /// The hash is `sha256(x || y || salt)`, where `x` and `y` are single bytes.
+    CommitOpening {
+        hash: Binary,
+        credentials: Credentials,
+    },
+    /// Reveal the opening shot committed to earlier
+    RevealOpening {
+        coords: BoundedCoords,
+        salt: String,
+        credentials: Credentials,
+    },
+    /// Commit to the next shot, in games played with committed shots.
+    ///
+    /// The hash is `sha256(x || y || salt)`, like for opening shots.
+    CommitShot {
+        hash: Binary,
+        credentials: Credentials,
+    },
+    /// Reveal the shot committed to earlier, in a later block, making it the pending shot
+    RevealShot {
+        coords: BoundedCoords,
+        salt: String,
+        /// A public comment on the shot, shown in the game status until the next shot
+        #[serde(default)]
+        comment: Option<Comment>,
+        credentials: Credentials,
+    },
+    /// Place a herd that arrived as a reinforcement, in games played with reinforcements.
+    /// Players have to place due reinforcements before they shoot.
+    PlaceHerd {
+        herd: Herd,
+        credentials: Credentials,
+    },
+    /// Donate the sent funds to the treasury, which funds prize pools
+    Donate {},
+}
+
+impl HandleMsg {
+    /// Whether this message may be sent with funds. Its handler checks the amount.
+    pub fn takes_funds(&self) -> bool {
+        matches!(
+            self,
+            HandleMsg::NewGame { .. }
+                | HandleMsg::NewGameFromTemplate { .. }
+                | HandleMsg::Join { .. }
+                | HandleMsg::Donate {}
+        )
+    }
+
+    /// The name this message is sent under, as the key of its JSON object
+    pub fn name(&self) -> &'static str {
+        match self {
+            HandleMsg::NewGame { .. } => "new_game",
+            HandleMsg::SaveTemplate { .. } => "save_template",
+            HandleMsg::DeleteTemplate { .. } => "delete_template",
+            HandleMsg::NewGameFromTemplate { .. } => "new_game_from_template",
+            HandleMsg::CancelGame { .. } => "cancel_game",
+            HandleMsg::CollectDeposit { .. } => "collect_deposit",
+            HandleMsg::RefundStakes { .. } => "refund_stakes",
+            HandleMsg::NewPracticeGame { .. } => "new_practice_game",
+            HandleMsg::Join { .. } => "join",
+            HandleMsg::Shoot { .. } => "shoot",
+            HandleMsg::Confirm { .. } => "confirm",
+            HandleMsg::ConfirmBlind { .. } => "confirm_blind",
+            HandleMsg::Pass { .. } => "pass",
+            HandleMsg::RequestTakeback { .. } => "request_takeback",
+            HandleMsg::GrantTakeback { .. } => "grant_takeback",
+            HandleMsg::Emote { .. } => "emote",
+            HandleMsg::AllowObserver { .. } => "allow_observer",
+            HandleMsg::RevokeObserver { .. } => "revoke_observer",
+            HandleMsg::CommitOpening { .. } => "commit_opening",
+            HandleMsg::RevealOpening { .. } => "reveal_opening",
+            HandleMsg::CommitShot { .. } => "commit_shot",
+            HandleMsg::RevealShot { .. } => "reveal_shot",
+            HandleMsg::PlaceHerd { .. } => "place_herd",
+            HandleMsg::Donate { .. } => "donate",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    /// Get a description of my pasture
+    /// Get a description of my pasture, and of the shots fired at it
     MyPasture { credentials: Credentials },
     /// Get the list of shots that I've made so far, and which ones have hit enemy sheep.
     MyShots { credentials: Credentials },
     /// Get the coordinate of the last shot made by the opponent
     LastShot { credentials: Credentials },
+    /// Get the version of the contract, and what it supports
+    Version {},
+    /// List the board presets that games can be created with
+    Presets {},
+    /// List every way in which a pasture breaks the rules of a board preset, so it can be
+    /// fixed before joining a game. Defaults to the "classic" preset.
+    ValidatePasture {
+        pasture: Pasture,
+        preset: Option<String>,
+    },
+    /// Get public information about the running turn of a game
+    TurnInfo { game: GameName },
+    /// Look up the confirmed shots at a cell, both in my pasture and in the opponent's
+    ShotInfo {
+        coords: BoundedCoords,
+        credentials: Credentials,
+    },
+    /// List the actions I may take in my game right now
+    LegalActions { credentials: Credentials },
+    /// Watch both pastures of a game, with the observer key its players agreed on
+    ObserverView { game: GameName, key: Password },
+    /// Get a minimal summary of the running turn of a game, for scoreboards that poll
+    /// many games. Anyone can query this.
+    TurnSummary { game: GameName },
+    /// Get the progress of a game, without revealing where any sheep are.
+    /// Anyone can query this.
+    GameStatus { game: GameName },
+    /// Get the optional rules a game is played with, whether or not it started
+    Rules { game: GameName },
+    /// List saved templates, in the order they were first saved.
+    ///
+    /// Pages cover a range of list indexes, so they can hold fewer templates than `limit`
+    /// when templates were deleted.
+    Templates {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// List games, oldest first, so clients can find games to join or watch. Practice
+    /// games aren't listed.
+    ///
+    /// Pages cover a range of list indexes, so they can hold fewer games than `limit` when
+    /// games were removed or don't match `status_filter`.
+    ListGames {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+        status_filter: Option<LobbyStatus>,
+    },
+    /// List finished games with both of their pastures revealed, oldest first
+    Archive {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Review the archived game at `index`, as both pastures looked after the first `shots`
+    /// shots fired at them
+    #[cfg(feature = "analysis")]
+    Analysis { index: u32, shots: u32 },
+    /// Get the latest finished game with this name from the archive, with the digest of
+    /// its transcript, so anyone can check a copy of the game against the chain
+    Transcript { game: GameName },
+    /// Get the lifetime score of the player at address `a` against the one at address `b`
+    HeadToHead { a: HumanAddr, b: HumanAddr },
+    /// Get the amounts donated and forfeited to the treasury, and the log of donations to it
+    Sponsors {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Run a handle message against the current state without keeping any of its changes,
+    /// to see whether it would succeed and what it would do.
+    ///
+    /// The message is handled as if `sender` sent it with `sent_funds` at block `height`.
+    /// The contract doesn't know its own address in queries, so messages sending funds
+    /// from it leave the address empty.
+    Simulate {
+        msg: Box<HandleMsg>,
+        sender: HumanAddr,
+        #[serde(default)]
+        sent_funds: Vec<Coin>,
+        height: u64,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Credentials {
-    pub game: String,
+    pub game: GameName,
+    pub username: Username,
+    pub password: Password,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SponsorsResponse {
+    /// The total amount donated, per denomination
+    pub balance: Vec<Coin>,
+    /// The total amount of lobby deposits forfeited to the treasury, per denomination
+    pub forfeited: Vec<Coin>,
+    pub sponsorships: Vec<Sponsorship>,
+}
+
+/// What a simulated handle message would do
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateResponse {
+    /// The error the message would fail with, if it fails
+    pub error: Option<String>,
+    /// The messages the contract would send
+    pub messages: Vec<CosmosMsg>,
+    pub log: Vec<LogAttribute>,
+}
+
+/// The version of a deployed contract, so clients can tell which features it supports
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VersionResponse {
+    /// The version of the contract crate
+    pub version: String,
+    /// The layout version game state is stored with
+    pub state_version: u8,
+    /// The versions of `GameRules` that games can be played with
+    pub rules_versions: Vec<u8>,
+    /// The cargo features the contract was built with
+    pub features: Vec<String>,
+}
+
+/// Public information about the running turn of a game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TurnInfo {
+    /// The number of turns resolved so far
+    pub turns: u32,
+    /// The username of the player whose turn it is to shoot
+    pub player: String,
+    /// Whether a shot is waiting to be confirmed
+    pub shot_pending: bool,
+    /// The block height by which the turn should be over, if the game has a turn clock
+    pub deadline: Option<u64>,
+    pub rules: GameRules,
+}
+
+/// A review of an archived game, partway through
+#[cfg(feature = "analysis")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AnalysisResponse {
+    pub winner: PastureAnalysis,
+    pub loser: PastureAnalysis,
+}
+
+/// How a pasture looked to the player shooting at it
+#[cfg(feature = "analysis")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PastureAnalysis {
+    /// The amount of shots fired at the pasture so far
+    pub shots: u32,
+    /// The herds that weren't sunk yet
+    pub remaining_fleet: Vec<FleetEntry>,
+    /// For every cell, the placements of remaining herds that could cover it, indexed by
+    /// row first
+    pub placements: Vec<Vec<u32>>,
+}
+
+/// The reactions players can send during their turn
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Emote {
+    Wave,
+    Cheer,
+    Laugh,
+    Gasp,
+    Think,
+    GoodGame,
+}
+
+impl Emote {
+    /// The code frontends receive for this emote
+    pub fn code(self) -> &'static str {
+        match self {
+            Emote::Wave => "wave",
+            Emote::Cheer => "cheer",
+            Emote::Laugh => "laugh",
+            Emote::Gasp => "gasp",
+            Emote::Think => "think",
+            Emote::GoodGame => "good_game",
+        }
+    }
+}
+
+/// A finished game, with the digest of its transcript
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TranscriptResponse {
+    /// The index of the game in the archive
+    pub index: u32,
+    /// The SHA-256 hash of `game` serialized as JSON
+    pub digest: Binary,
+    pub game: ArchivedGame,
+}
+
+/// A page of the game list
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListGamesResponse {
+    pub games: Vec<GameListing>,
+    /// The last index the page covered, to start the next page after. `None` once the
+    /// page is past the end of the list.
+    pub last_index: Option<u32>,
+}
+
+/// A page of the template list
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TemplatesResponse {
+    pub templates: Vec<Template>,
+    /// The last index the page covered, to start the next page after. `None` once the
+    /// page is past the end of the list.
+    pub last_index: Option<u32>,
+}
+
+/// A game in the game list
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameListing {
+    /// The index the game is listed under
+    pub index: u32,
+    pub name: String,
+    /// The amount of players who joined the game
+    pub players: u32,
+    pub status: LobbyStatus,
+    /// The pasture the game is played on, and the fleet to place in it
+    pub board: BoardConfig,
+}
+
+/// How far along a listed game is
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LobbyStatus {
+    /// The game is waiting for players to join
+    Open,
+    /// Both players joined, and nobody won yet
+    Running,
+    /// A player won the game
+    Finished,
+}
+
+/// The confirmed shots at a single cell, on both sides of a game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShotInfo {
+    /// The shot the opponent fired at the cell in my pasture
+    pub received: Option<ShotRecord>,
+    /// The shot I fired at the cell in the opponent's pasture
+    pub fired: Option<ShotRecord>,
+}
+
+/// A confirmed shot at a cell
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct ShotRecord {
+    /// The position of the shot among those fired at the same pasture, starting at 1
+    pub number: u32,
+    /// What the shot found, as the shooter sees it: a miss, a hit, or a sheep of a
+    /// sunk herd
+    pub state: CellState,
+}
+
+/// Both sides of a game, as shown to observers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ObserverView {
+    /// The players, in the order they joined
+    pub sides: Vec<ObservedSide>,
+}
+
+/// One player's pasture, as shown to observers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ObservedSide {
     pub username: String,
-    pub password: String,
+    pub pasture: MyPastureResponse,
+}
+
+/// A minimal summary of the running turn of a game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TurnSummary {
+    /// The number of turns resolved so far
+    pub turns: u32,
+    pub phase: Phase,
+    /// The block height of the last action either player took, if any
+    pub last_action: Option<u64>,
+    /// The amount of herds each player has left, in the order they joined
+    pub herds_left: Vec<u32>,
+}
+
+/// The public progress of a game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameStatus {
+    /// The number of turns resolved so far
+    pub turns: u32,
+    /// The players who joined so far, in the order they joined
+    pub sides: Vec<SideStatus>,
+    /// The username of the winner, once the game is over
+    pub winner: Option<String>,
+    /// The language the players are expected to speak, if the game was tagged with one
+    pub locale: Option<String>,
+    /// The stake each player puts up, if the game is played for a wager
+    pub wager: Option<Coin>,
+}
+
+/// The public progress of one player in a game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SideStatus {
+    pub username: String,
+    /// The amount of the player's herds that were sunk
+    pub sunk_herds: u32,
+    /// The player's herds that still have sheep left, counted by length
+    pub remaining_fleet: Vec<FleetEntry>,
+    /// The points the player scored, in games decided by points
+    pub points: Option<u32>,
+    /// The comment the player attached to their latest shot
+    pub comment: Option<String>,
+    /// The shots the player has left, in games with limited ammunition
+    pub ammo: Option<u32>,
+}
+
+/// A player's own pasture, overlaid with the shots fired at it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MyPastureResponse {
+    /// Width and height of the pasture
+    pub size: u8,
+    pub herds: Vec<Herd>,
+    /// The cells of the pasture, indexed as `cells[y][x]`
+    pub cells: Vec<Vec<CellState>>,
+}
+
+/// What is known about a single cell of a pasture, so clients don't have to derive it
+/// from several flags
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CellState {
+    /// Nothing is known about the cell. In the owner's view, an empty cell nobody shot at.
+    Unknown,
+    /// A shot at the cell found no sheep
+    Miss,
+    /// A shot at the cell hit a sheep of a herd that still has sheep left
+    Hit,
+    /// The cell holds a sheep of a herd that had all of its sheep shot
+    Sunk,
+    /// The cell holds one of the owner's sheep, which wasn't shot
+    OwnSheep,
+    /// The cell holds one of the owner's sheep, which was shot
+    OwnSheepHit,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -52,3 +583,73 @@ pub struct Shots {
     pub hits: Vec<Coords>,
     pub misses: Vec<Coords>,
 }
+
+/// A contract registered to be notified about the progress of games
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Callback {
+    pub contract_addr: HumanAddr,
+    pub code_hash: String,
+}
+
+impl Callback {
+    /// Build the message that delivers `msg` to the callback contract
+    pub fn notify<T: Serialize>(&self, msg: &T) -> StdResult<CosmosMsg> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.contract_addr.clone(),
+            callback_code_hash: self.code_hash.clone(),
+            msg: to_binary(msg)?,
+            send: vec![],
+        }))
+    }
+}
+
+/// Messages sent to a game's callback contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackMsg {
+    /// A turn was resolved by the confirmation of its shot
+    TurnResolved {
+        game: String,
+        /// The number of turns resolved so far, including this one
+        turn: u32,
+        coords: Coords,
+        hit: bool,
+    },
+    /// The game is over
+    GameOver { game: String, stats: GameStats },
+}
+
+/// The statistics of a finished game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GameStats {
+    /// The amount of blocks from the start of the game to its end, for games that recorded
+    /// their start
+    pub duration: Option<u64>,
+    pub winner: PlayerStats,
+    pub loser: PlayerStats,
+}
+
+/// The statistics of one player in a finished game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlayerStats {
+    pub username: String,
+    /// The shots fired at the opponent's pasture
+    pub shots: u32,
+    /// The shots that hit a sheep
+    pub hits: u32,
+    /// Hits per 10,000 shots
+    pub accuracy: u32,
+}
+
+/// Messages sent to the mailbox contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MailboxMsg {
+    /// Tell a player that it's their turn in a game
+    YourTurn {
+        recipient: HumanAddr,
+        game: String,
+        /// The number of turns resolved so far
+        turn: u32,
+    },
+}
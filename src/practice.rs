@@ -0,0 +1,38 @@
+use cosmwasm_std::{HumanAddr, StdResult};
+
+use crate::bounded::{Password, Username};
+use crate::state::{Coords, Herd, Orientation, Pasture, Player};
+
+/// The username the scripted opponent of practice games plays under
+pub const TUTOR: &str = "tutor";
+
+/// The scripted opponent of a practice game, with a fixed layout on the classic board
+pub fn tutor() -> StdResult<Player> {
+    let pasture = Pasture::new(
+        vec![
+            Herd::new(1, 1, 2, Orientation::Horizontal),
+            Herd::new(4, 3, 3, Orientation::Vertical),
+            Herd::new(7, 0, 3, Orientation::Vertical),
+            Herd::new(0, 8, 4, Orientation::Horizontal),
+            Herd::new(9, 4, 5, Orientation::Vertical),
+        ],
+        vec![],
+    );
+
+    Ok(Player::new(
+        Username::new(TUTOR.to_string())?,
+        Password::default(),
+        pasture,
+        HumanAddr::default(),
+    ))
+}
+
+/// The `index`th shot of the tutor, which sweeps the pasture row by row
+pub fn tutor_shot(index: u32, size: u8) -> Coords {
+    let size = u32::from(size);
+    let cell = index % (size * size);
+    // Both values are smaller than `size`, which fits in a `u8`
+    #[allow(clippy::cast_possible_truncation)]
+    let (x, y) = ((cell % size) as u8, (cell / size) as u8);
+    Coords::new(x, y)
+}
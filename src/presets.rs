@@ -0,0 +1,194 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult};
+
+/// The preset used when a game doesn't ask for a specific one
+pub const DEFAULT_PRESET: &str = "classic";
+/// The largest pasture a board may have
+pub const MAX_PASTURE_SIZE: u8 = 25;
+/// The share of the pasture a derived fleet covers, in percent, as on the classic board
+const FLEET_COVERAGE: u32 = 17;
+/// The herd lengths a derived fleet is made of, repeated until it has enough sheep
+const FLEET_PATTERN: [u8; 5] = [5, 4, 3, 3, 2];
+
+/// The dimensions of the pasture, and the herds each player has to place in it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BoardConfig {
+    /// Width and height of the pasture
+    pub size: u8,
+    /// The herds each player must place
+    pub fleet: Vec<FleetEntry>,
+}
+
+impl BoardConfig {
+    /// How many herds of this length the fleet is made of
+    pub fn expected_count(&self, length: u8) -> u32 {
+        self.fleet
+            .iter()
+            .find(|entry| entry.length == length)
+            .map_or(0, |entry| entry.count)
+    }
+
+    /// The amount of herds in the fleet
+    pub fn herd_count(&self) -> u32 {
+        self.fleet.iter().map(|entry| entry.count).sum()
+    }
+
+    /// Check that herds of this fleet can actually be placed in a pasture of this size
+    pub fn validate(&self) -> StdResult<()> {
+        if self.size == 0 || self.size > MAX_PASTURE_SIZE {
+            return Err(StdError::generic_err(format!(
+                "Pasture size must be between 1 and {}",
+                MAX_PASTURE_SIZE
+            )));
+        }
+        if self.fleet.is_empty() {
+            return Err(StdError::generic_err("The fleet has no herds".to_string()));
+        }
+
+        let mut sheep = 0_u32;
+        for (index, entry) in self.fleet.iter().enumerate() {
+            if entry.length == 0 || entry.length > self.size {
+                return Err(StdError::generic_err(format!(
+                    "Herds of length {} don't fit in a pasture of size {}",
+                    entry.length, self.size
+                )));
+            }
+            if entry.count == 0 {
+                return Err(StdError::generic_err(format!(
+                    "The fleet lists no herds of length {}",
+                    entry.length
+                )));
+            }
+            if self.fleet[..index]
+                .iter()
+                .any(|other| other.length == entry.length)
+            {
+                return Err(StdError::generic_err(format!(
+                    "Herds of length {} are listed more than once",
+                    entry.length
+                )));
+            }
+            sheep = sheep.saturating_add(u32::from(entry.length).saturating_mul(entry.count));
+        }
+        if sheep > u32::from(self.size) * u32::from(self.size) {
+            return Err(StdError::generic_err(format!(
+                "{} sheep don't fit in a pasture of size {}",
+                sheep, self.size
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        classic()
+    }
+}
+
+/// A number of herds of the same length
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct FleetEntry {
+    /// Amount of sheep in each herd
+    pub length: u8,
+    /// Amount of herds
+    pub count: u32,
+}
+
+/// A named board configuration that games can be created with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Preset {
+    pub name: String,
+    pub board: BoardConfig,
+}
+
+/// All the presets games can be created with
+pub fn presets() -> Vec<Preset> {
+    vec![
+        Preset {
+            name: "classic".to_string(),
+            board: classic(),
+        },
+        Preset {
+            name: "mini".to_string(),
+            board: mini(),
+        },
+        Preset {
+            name: "mega".to_string(),
+            board: mega(),
+        },
+    ]
+}
+
+/// Look up a preset by name
+pub fn preset(name: &str) -> StdResult<BoardConfig> {
+    presets()
+        .into_iter()
+        .find(|preset| preset.name == name)
+        .map(|preset| preset.board)
+        .ok_or_else(|| StdError::generic_err(format!("Unknown preset {:?}", name)))
+}
+
+/// A fleet that suits a pasture of this size, for boards that only specify their size.
+///
+/// The fleet covers about `FLEET_COVERAGE` percent of the pasture, with the herds of the
+/// classic fleet repeated until it's large enough. Herds are cut down to fit the pasture,
+/// and the last one to land on the exact amount of sheep.
+pub fn fleet_for_board(size: u8) -> Vec<FleetEntry> {
+    if size == 0 {
+        return vec![];
+    }
+
+    let cells = u32::from(size) * u32::from(size);
+    let mut sheep_left = ((cells * FLEET_COVERAGE + 50) / 100).max(1);
+    let mut entries: Vec<FleetEntry> = vec![];
+    for &length in FLEET_PATTERN.iter().cycle() {
+        if sheep_left == 0 {
+            break;
+        }
+        let length = length.min(size);
+        let length = u32::from(length).min(sheep_left) as u8;
+        sheep_left -= u32::from(length);
+        match entries.iter_mut().find(|entry| entry.length == length) {
+            Some(entry) => entry.count += 1,
+            None => entries.push(FleetEntry { length, count: 1 }),
+        }
+    }
+    entries.sort_by_key(|entry| entry.length);
+
+    entries
+}
+
+fn fleet(entries: &[(u8, u32)]) -> Vec<FleetEntry> {
+    entries
+        .iter()
+        .map(|&(length, count)| FleetEntry { length, count })
+        .collect()
+}
+
+/// 10×10 pasture with 5 herds
+fn classic() -> BoardConfig {
+    BoardConfig {
+        size: 10,
+        fleet: fleet(&[(2, 1), (3, 2), (4, 1), (5, 1)]),
+    }
+}
+
+/// 6×6 pasture with 3 herds
+fn mini() -> BoardConfig {
+    BoardConfig {
+        size: 6,
+        fleet: fleet(&[(2, 1), (3, 1), (4, 1)]),
+    }
+}
+
+/// 15×15 pasture with 8 herds
+fn mega() -> BoardConfig {
+    BoardConfig {
+        size: 15,
+        fleet: fleet(&[(2, 2), (3, 2), (4, 2), (5, 1), (6, 1)]),
+    }
+}
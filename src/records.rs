@@ -0,0 +1,68 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdResult, Storage};
+
+use crate::storage;
+
+/// The lifetime score of one player against another, as seen by the first of them.
+///
+/// Players are identified by the address they joined games with, since usernames are
+/// picked anew for every game.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct HeadToHead {
+    /// Games the first player won against the second
+    pub wins: u32,
+    /// Games the first player lost against the second
+    pub losses: u32,
+}
+
+impl HeadToHead {
+    /// Load the score of `player` against `rival`
+    pub fn load<S: Storage>(storage: &S, player: &HumanAddr, rival: &HumanAddr) -> StdResult<Self> {
+        let (key, flipped) = pair_key(player, rival);
+        let record = storage::head_to_head_read(storage)
+            .may_load(&key)?
+            .unwrap_or_default();
+
+        Ok(if flipped { record.flipped() } else { record })
+    }
+
+    /// Count a game won by `winner` against `loser`
+    pub fn record_win<S: Storage>(
+        storage: &mut S,
+        winner: &HumanAddr,
+        loser: &HumanAddr,
+    ) -> StdResult<()> {
+        let mut record = Self::load(storage, winner, loser)?;
+        record.wins += 1;
+
+        let (key, flipped) = pair_key(winner, loser);
+        let record = if flipped { record.flipped() } else { record };
+        storage::head_to_head(storage).save(&key, &record)
+    }
+
+    fn flipped(self) -> Self {
+        Self {
+            wins: self.losses,
+            losses: self.wins,
+        }
+    }
+}
+
+/// The key a pair of players is stored under, which is the same whichever way round they
+/// are given, and whether the stored record is the other way round
+fn pair_key(player: &HumanAddr, rival: &HumanAddr) -> (Vec<u8>, bool) {
+    let flipped = player.as_str() > rival.as_str();
+    let (first, second) = if flipped {
+        (rival, player)
+    } else {
+        (player, rival)
+    };
+
+    // Prefixing the length of the first address keeps keys of different pairs apart
+    let mut key = (first.len() as u32).to_be_bytes().to_vec();
+    key.extend_from_slice(first.as_str().as_bytes());
+    key.extend_from_slice(second.as_str().as_bytes());
+    (key, flipped)
+}
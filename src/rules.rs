@@ -0,0 +1,167 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult};
+
+/// The current version of `GameRules`
+///
+/// * 1 - opening salvo, shooting again on a hit and turn clocks
+/// * 2 - sudden death
+/// * 3 - scoring by points
+/// * 4 - distinct players
+/// * 5 - committed shots
+/// * 6 - victory conditions, which replace scoring by points
+/// * 7 - reinforcements
+/// * 8 - sender authentication
+/// * 9 - passing
+/// * 10 - limited ammunition
+/// * 11 - strict confirms
+pub const RULES_VERSION: u8 = 11;
+
+/// The points a player scores for every sheep they hit, in games decided by points
+pub const HIT_POINTS: u32 = 1;
+/// The extra points a player scores for every herd they sink, in games decided by points
+pub const SINK_BONUS: u32 = 3;
+/// The amount of turns after which players get another herd, in games with reinforcements
+pub const REINFORCEMENT_INTERVAL: u32 = 5;
+
+/// The optional rules a game is played with
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct GameRules {
+    /// The version of the rules the game was created with. Set by the contract.
+    #[serde(default)]
+    pub version: u8,
+    /// Start the game with a blind opening salvo fired by both players at once
+    #[serde(default)]
+    pub opening_salvo: bool,
+    /// Let a player who hits a sheep shoot again, instead of passing the turn
+    #[serde(default)]
+    pub shoot_again_on_hit: bool,
+    /// The amount of blocks each turn may take.
+    ///
+    /// The deadline is announced to clients, but not enforced by the contract yet.
+    pub turn_clock: Option<u64>,
+    /// How the game speeds up once both players are down to their last herd
+    #[serde(default)]
+    pub sudden_death: Option<SuddenDeath>,
+    /// How the game is won
+    #[serde(default)]
+    pub victory: VictoryCondition,
+    /// Keep one player from taking both seats, by rejecting a second player who reuses
+    /// the first player's password or joins from the same address
+    #[serde(default)]
+    pub distinct_players: bool,
+    /// Make shooters commit to the hash of each shot, and reveal it in a later block, so a
+    /// pending transaction doesn't give the target away
+    #[serde(default)]
+    pub committed_shots: bool,
+    /// Start the game with half of the fleet, rounded up, and let players place another
+    /// herd every `REINFORCEMENT_INTERVAL` turns, on cells that weren't shot yet
+    #[serde(default)]
+    pub reinforcements: bool,
+    /// Only accept moves sent from the address each player joined from, so a leaked
+    /// password is only good for reading the game. Queries still authenticate with the
+    /// password, because they have no sender.
+    #[serde(default)]
+    pub sender_auth: bool,
+    /// Let players pass their turn without shooting. Only games that aren't won by
+    /// clearing pastures allow it, as holding fire can only pay off there.
+    #[serde(default)]
+    pub passing: bool,
+    /// The amount of shots each player may fire in the game. A player who ran out sits out
+    /// while the opponent still has shots left. Once both players are out of shots, the game
+    /// is decided by points or survival in games played for them, and otherwise by sheep
+    /// hit. Ties go to the player who landed their last hit in fewer shots, and then to the
+    /// player who shot second.
+    #[serde(default)]
+    pub ammo: Option<u32>,
+    /// Keep the pending shot hidden from the player being shot at until it's confirmed.
+    /// They confirm it blindly, so they can't decide whether to confirm, or when, by where
+    /// the shot landed.
+    #[serde(default)]
+    pub strict_confirms: bool,
+}
+
+/// The ways a game can be won.
+///
+/// Clearing the opponent's pasture wins under every condition. Conditions checked after a
+/// number of rounds, in each of which both players take a turn, are checked again after
+/// every further round while they are tied.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VictoryCondition {
+    /// Clear the opponent's pasture
+    EliminateAll,
+    /// Be the first to hit this many sheep
+    FirstToHits { hits: u32 },
+    /// Have the most points after this many rounds. Every sheep hit is worth `HIT_POINTS`,
+    /// and every herd sunk `SINK_BONUS` on top.
+    PointsAfterRounds { rounds: u32 },
+    /// Have the most sheep left after this many rounds
+    SurviveRounds { rounds: u32 },
+}
+
+impl Default for VictoryCondition {
+    fn default() -> Self {
+        Self::EliminateAll
+    }
+}
+
+/// The ways a game can speed up once both players are down to their last herd
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SuddenDeath {
+    /// Shrink the pastures, by revealing every cell on their edge as if it was shot
+    OuterRing,
+    /// Let players fire two shots every turn
+    DoubleShots,
+}
+
+impl GameRules {
+    /// Check that the rules can be played with, both on their own and together
+    pub fn validate(&self) -> StdResult<()> {
+        if self.turn_clock == Some(0) {
+            return Err(StdError::generic_err(
+                "Turns must last at least one block".to_string(),
+            ));
+        }
+
+        match self.victory {
+            VictoryCondition::FirstToHits { hits: 0 } => {
+                return Err(StdError::generic_err(
+                    "Games won by hits must take at least one hit".to_string(),
+                ));
+            }
+            VictoryCondition::PointsAfterRounds { rounds: 0 }
+            | VictoryCondition::SurviveRounds { rounds: 0 } => {
+                return Err(StdError::generic_err(
+                    "Games decided after a number of rounds must last at least one".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        if self.reinforcements
+            && (self.victory != VictoryCondition::EliminateAll || self.sudden_death.is_some())
+        {
+            return Err(StdError::generic_err(
+                "Reinforcements only work in games won by clearing pastures, without sudden death"
+                    .to_string(),
+            ));
+        }
+
+        if self.ammo == Some(0) {
+            return Err(StdError::generic_err(
+                "Players need at least one shot".to_string(),
+            ));
+        }
+
+        if self.passing && self.victory == VictoryCondition::EliminateAll {
+            return Err(StdError::generic_err(
+                "Passing is only allowed in games won by hits, points or survival".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -2,15 +2,281 @@ use derive_more::Display;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::msg::{Credentials, Shots};
-use cosmwasm_std::{StdError, StdResult, Storage};
-use cosmwasm_storage::{prefixed, prefixed_read, singleton, singleton_read};
+use crate::bounded::{Comment, Password, Username};
+use crate::msg::{
+    Callback, CellState, Credentials, Deprecation, GameStats, MyPastureResponse, PlayerStats,
+    ShotRecord, Shots,
+};
+use crate::practice::TUTOR;
+use crate::presets::{BoardConfig, FleetEntry};
+use crate::rules::{
+    GameRules, SuddenDeath, VictoryCondition, HIT_POINTS, REINFORCEMENT_INTERVAL, RULES_VERSION,
+    SINK_BONUS,
+};
+use crate::storage::{self, Pagination};
+use cosmwasm_std::{Binary, Coin, HumanAddr, StdError, StdResult, Storage, Uint128};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::ops::Range;
 use std::ops::{AddAssign, Deref, DerefMut};
 
-const GAMES: &[u8] = b"games";
+/// The current layout version of `GameState`
+///
+/// * 0 - shots were stored on the pasture of the player who fired them
+/// * 1 - shots are stored on the pasture they were fired at
+/// * 2 - optional rules are stored together in `rules`
+pub const STATE_VERSION: u8 = 2;
+
+/// The amount of blocks a game lasts by default, roughly a week
+pub const DEFAULT_GAME_LIFETIME: u64 = 100_800;
+/// The amount of blocks a player waits between actions by default
+pub const DEFAULT_ACTION_COOLDOWN: u64 = 1;
+
+/// Contract-wide settings, set at initialization
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// The amount of blocks games last by default before they expire
+    pub game_lifetime: u64,
+    /// The amount of blocks a player has to wait between two actions in a game
+    #[serde(default = "default_action_cooldown")]
+    pub action_cooldown: u64,
+    /// A mailbox contract to tell players when it's their turn
+    #[serde(default)]
+    pub mailbox: Option<Callback>,
+    /// A deposit that opening a lobby requires, which is refunded once the game starts
+    #[serde(default)]
+    pub lobby_deposit: Option<Coin>,
+    /// Words that names and comments can't contain, in lowercase
+    #[serde(default)]
+    pub banned_words: Vec<String>,
+    /// The only contracts games may send callbacks to, or `None` to allow any
+    #[serde(default)]
+    pub allowed_callbacks: Option<Vec<HumanAddr>>,
+    /// Messages that are being phased out, and the heights they stop working at
+    #[serde(default)]
+    pub deprecations: Vec<Deprecation>,
+}
+
+fn default_action_cooldown() -> u64 {
+    DEFAULT_ACTION_COOLDOWN
+}
+
+impl Config {
+    pub fn save<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
+        storage::config(storage).save(self)
+    }
+
+    pub fn load<S: Storage>(storage: &S) -> StdResult<Self> {
+        storage::config_read(storage).load()
+    }
+
+    /// Reject names and comments that contain a banned word, in any case
+    pub fn check_words(&self, text: &str) -> StdResult<()> {
+        let text = text.to_lowercase();
+        if self
+            .banned_words
+            .iter()
+            .any(|word| text.contains(word.as_str()))
+        {
+            return Err(StdError::generic_err(
+                "This text contains a word that isn't allowed".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Reject callbacks to contracts the operator didn't allow
+    pub fn check_callback(&self, callback: &Callback) -> StdResult<()> {
+        match &self.allowed_callbacks {
+            Some(allowed) if !allowed.contains(&callback.contract_addr) => {
+                Err(StdError::generic_err(format!(
+                    "Games can't send callbacks to {}",
+                    callback.contract_addr
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The deprecation of a message that still works at this height, if it has one.
+    /// Once its sunset height is reached, the message is refused in favor of its replacement.
+    pub fn check_deprecation(&self, message: &str, height: u64) -> StdResult<Option<&Deprecation>> {
+        match self.deprecations.iter().find(|d| d.message == message) {
+            Some(deprecation) if height >= deprecation.sunset => {
+                Err(StdError::generic_err(format!(
+                    "The {} message was retired at height {}. Send {} instead",
+                    message, deprecation.sunset, deprecation.replacement
+                )))
+            }
+            found => Ok(found),
+        }
+    }
+}
+
+/// The role a player has in the current turn of a game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// The player whose turn it is to shoot
+    Player,
+    /// The player being shot at, who confirms the shot
+    Opponent,
+}
+
+/// The kind of action a game is waiting for
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Both players commit to and reveal their opening shots
+    Opening,
+    /// The player whose turn it is picks a shot
+    Shooting,
+    /// The player being shot at confirms the pending shot
+    Confirming,
+    /// A player won, and the game takes no more actions
+    Finished,
+}
+
+/// The kinds of action players take while a game is being played
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    CommitOpening,
+    RevealOpening,
+    Shoot,
+    CommitShot,
+    RevealShot,
+    PlaceHerd,
+    Pass,
+    Confirm,
+    ConfirmBlind,
+    RequestTakeback,
+    GrantTakeback,
+}
+
+impl ActionKind {
+    /// Every kind of action, in the order they are listed in
+    pub const ALL: [ActionKind; 11] = [
+        ActionKind::CommitOpening,
+        ActionKind::RevealOpening,
+        ActionKind::Shoot,
+        ActionKind::CommitShot,
+        ActionKind::RevealShot,
+        ActionKind::PlaceHerd,
+        ActionKind::Pass,
+        ActionKind::Confirm,
+        ActionKind::ConfirmBlind,
+        ActionKind::RequestTakeback,
+        ActionKind::GrantTakeback,
+    ];
+
+    /// The phase a game has to be in for this action
+    pub fn phase(self) -> Phase {
+        match self {
+            ActionKind::CommitOpening | ActionKind::RevealOpening => Phase::Opening,
+            ActionKind::Shoot
+            | ActionKind::CommitShot
+            | ActionKind::RevealShot
+            | ActionKind::PlaceHerd
+            | ActionKind::Pass => Phase::Shooting,
+            ActionKind::Confirm
+            | ActionKind::ConfirmBlind
+            | ActionKind::RequestTakeback
+            | ActionKind::GrantTakeback => Phase::Confirming,
+        }
+    }
+
+    /// The role of the player who takes this action, or `None` if both players take it
+    pub fn actor(self) -> Option<Role> {
+        match self {
+            ActionKind::CommitOpening | ActionKind::RevealOpening => None,
+            // The shooter may ask for a takeback while the shot is pending
+            ActionKind::Shoot
+            | ActionKind::CommitShot
+            | ActionKind::RevealShot
+            | ActionKind::PlaceHerd
+            | ActionKind::Pass
+            | ActionKind::RequestTakeback => Some(Role::Player),
+            ActionKind::Confirm | ActionKind::ConfirmBlind | ActionKind::GrantTakeback => {
+                Some(Role::Opponent)
+            }
+        }
+    }
+}
+
+/// Game settings saved by an organizer, so recurring games can be created from them by name
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Template {
+    pub name: String,
+    /// The only address allowed to change or delete the template
+    pub organizer: HumanAddr,
+    pub board: BoardConfig,
+    #[serde(default)]
+    pub rules: GameRules,
+    /// The amount of blocks games last. Uses the contract default if not set.
+    pub lifetime: Option<u64>,
+}
+
+impl Template {
+    pub fn save<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
+        storage::templates(storage).save(self.name.as_bytes(), self)?;
+
+        let key = self.name.as_bytes();
+        if storage::template_list_index_read(storage)
+            .may_load(key)?
+            .is_none()
+        {
+            let size = template_list_size(storage)?;
+            storage::template_list(storage).save(&size.to_be_bytes(), &self.name)?;
+            storage::template_list_index(storage).save(key, &size)?;
+            storage::template_list_size(storage).save(&(size + 1))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn load<S: Storage>(storage: &S, name: &str) -> StdResult<Self> {
+        Self::may_load(storage, name)?.ok_or_else(|| {
+            StdError::generic_err(format!("Template named {:?} doesn't exist", name))
+        })
+    }
+
+    pub fn may_load<S: Storage>(storage: &S, name: &str) -> StdResult<Option<Self>> {
+        storage::templates_read(storage).may_load(name.as_bytes())
+    }
+
+    pub fn remove<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
+        let key = self.name.as_bytes();
+        storage::templates(storage).remove(key);
+        if let Some(index) = storage::template_list_index_read(storage).may_load(key)? {
+            storage::template_list(storage).remove(&index.to_be_bytes());
+            storage::template_list_index(storage).remove(key);
+        }
+
+        Ok(())
+    }
+}
 
-const PASTURE_SIZE: u8 = 10;
+/// The indexes on a page of the template list, which holds templates in the order they
+/// were first saved
+pub fn template_page<S: Storage>(storage: &S, page: Pagination) -> StdResult<Range<u32>> {
+    Ok(page.range(template_list_size(storage)?))
+}
+
+/// The template listed under this index, or `None` if it was deleted
+pub fn listed_template<S: Storage>(storage: &S, index: u32) -> StdResult<Option<Template>> {
+    match storage::template_list_read(storage).may_load(&index.to_be_bytes())? {
+        Some(name) => Template::may_load(storage, &name),
+        None => Ok(None),
+    }
+}
+
+fn template_list_size<S: Storage>(storage: &S) -> StdResult<u32> {
+    storage::template_list_size_read(storage)
+        .may_load()
+        .map(Option::unwrap_or_default)
+}
 
 /// This type represents a game that has been correctly configured and has two players.
 #[derive(Clone, Debug)]
@@ -19,6 +285,149 @@ pub struct FullGame {
 }
 
 impl FullGame {
+    /// Find out which role the credentials have in the game.
+    ///
+    /// Credentials that match neither player are rejected with `StdError::Unauthorized`,
+    /// so every handle and query reports failed authentication the same way.
+    pub fn role_of(&self, credentials: &Credentials) -> StdResult<Role> {
+        // Nobody gets to play the tutor's side of a practice game
+        if self.is_practice() && credentials.username.as_str() == TUTOR {
+            Err(StdError::unauthorized())
+        } else if self.player().matches_credentials(credentials) {
+            Ok(Role::Player)
+        } else if self.opponent().matches_credentials(credentials) {
+            Ok(Role::Opponent)
+        } else {
+            Err(StdError::unauthorized())
+        }
+    }
+
+    /// The kind of action the game is waiting for
+    pub fn phase(&self) -> Phase {
+        if self.state.winner.is_some() {
+            Phase::Finished
+        } else if self.in_opening() {
+            Phase::Opening
+        } else if self.state.next_shot.is_some() {
+            Phase::Confirming
+        } else {
+            Phase::Shooting
+        }
+    }
+
+    /// Check that the player with this role may take this action now.
+    ///
+    /// Every rule about who may act when is kept here, so handlers and the actions offered
+    /// by `legal_actions` can't disagree. The only thing left to the handlers is checking
+    /// the cell a confirm names, which `confirm_shot` does.
+    pub fn check_action(&self, role: Role, action: ActionKind) -> StdResult<()> {
+        let current = self.phase();
+        if current != action.phase() {
+            let msg = match (current, action) {
+                (Phase::Finished, _) => "The game is over",
+                (Phase::Opening, _) => "The opening salvo must be resolved first",
+                (_, ActionKind::CommitOpening) | (_, ActionKind::RevealOpening) => {
+                    "This game has no pending opening salvo"
+                }
+                (Phase::Confirming, _) => "The pending shot must be confirmed first",
+                (Phase::Shooting, ActionKind::RequestTakeback) => {
+                    "There is no pending shot to take back"
+                }
+                (Phase::Shooting, _) => "There is no shot to confirm",
+            };
+            return Err(StdError::generic_err(msg.to_string()));
+        }
+
+        match action.actor() {
+            Some(actor) if role != actor => {
+                let msg = match action {
+                    ActionKind::Confirm | ActionKind::ConfirmBlind | ActionKind::GrantTakeback => {
+                        "You do not have permissions to confirm this shot"
+                    }
+                    ActionKind::RequestTakeback => "Only the shooter can take back a shot",
+                    _ => "It's not your turn",
+                };
+                return Err(StdError::generic_err(msg.to_string()));
+            }
+            _ => {}
+        }
+
+        let seat = match role {
+            Role::Player => self.state.turn as usize,
+            Role::Opponent => 1 - self.state.turn as usize,
+        };
+        let opening = self.state.opening.as_ref();
+        let committed = |seat: usize| {
+            opening
+                .and_then(|opening| opening.commitments[seat].as_ref())
+                .is_some()
+        };
+        let revealed = opening.and_then(|opening| opening.reveals[seat]).is_some();
+        let committed_shots = self.state.rules.committed_shots;
+
+        let refusal = match action {
+            ActionKind::CommitOpening if committed(seat) => {
+                Some("You already committed to an opening shot")
+            }
+            ActionKind::RevealOpening if !committed(0) || !committed(1) => {
+                Some("Both players must commit to an opening shot before revealing")
+            }
+            ActionKind::RevealOpening if revealed => Some("You already revealed your opening shot"),
+            ActionKind::Shoot | ActionKind::CommitShot | ActionKind::RevealShot
+                if self.reinforcement_due() =>
+            {
+                Some("Place your reinforcement before shooting")
+            }
+            ActionKind::Shoot if committed_shots => {
+                Some("Shots in this game have to be committed to first")
+            }
+            ActionKind::CommitShot if !committed_shots => {
+                Some("This game isn't played with committed shots")
+            }
+            ActionKind::CommitShot if self.state.shot_commitment.is_some() => {
+                Some("You already committed to a shot")
+            }
+            ActionKind::RevealShot if self.state.shot_commitment.is_none() => {
+                Some("There is no committed shot to reveal")
+            }
+            ActionKind::PlaceHerd if !self.reinforcement_due() => {
+                Some("No reinforcement is due yet")
+            }
+            ActionKind::Shoot | ActionKind::CommitShot if self.player().ammo == Some(0) => {
+                Some("You have no shots left")
+            }
+            ActionKind::Pass if !self.state.rules.passing => {
+                Some("This game doesn't allow passing")
+            }
+            ActionKind::Pass if self.state.shot_commitment.is_some() => {
+                Some("You already committed to a shot")
+            }
+            ActionKind::Confirm if self.state.rules.strict_confirms => {
+                Some("The pending shot is hidden in this game, so confirm it blindly")
+            }
+            ActionKind::ConfirmBlind if !self.state.rules.strict_confirms => {
+                Some("This game doesn't hide pending shots")
+            }
+            ActionKind::GrantTakeback if !self.state.takeback_requested => {
+                Some("No takeback was requested")
+            }
+            _ => None,
+        };
+        match refusal {
+            Some(msg) => Err(StdError::generic_err(msg.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// The actions the player with this role may take now
+    pub fn legal_actions(&self, role: Role) -> Vec<ActionKind> {
+        ActionKind::ALL
+            .iter()
+            .copied()
+            .filter(|&action| self.check_action(role, action).is_ok())
+            .collect()
+    }
+
     pub fn player(&self) -> &Player {
         let state = &self.game.state;
         &state.players[state.turn as usize]
@@ -45,43 +454,439 @@ impl FullGame {
         self.state.next_shot = Some(coords);
     }
 
+    /// Attach a public comment to the shot of the player whose turn it is, replacing the
+    /// one on their previous shot
+    pub fn set_comment(&mut self, comment: Option<Comment>) {
+        self.player_mut().comment = comment;
+    }
+
     pub fn next_shot(&self) -> Option<Coords> {
         self.state.next_shot
     }
 
+    /// The pending shot, as far as the player with this role may know it. Games with strict
+    /// confirms keep it from the player being shot at.
+    pub fn visible_shot(&self, role: Role) -> Option<Coords> {
+        if self.state.rules.strict_confirms && role == Role::Opponent {
+            None
+        } else {
+            self.state.next_shot
+        }
+    }
+
     /// Confirm the shot performed previously.
     ///
     /// We have to add this step to prevent players from running the game offline and checking all the slots themselves.
-    pub fn confirm_shot(&mut self, coords: Coords) {
-        self.player_mut().pasture.shots.push(coords);
+    /// `check_action` decides whether a confirm is allowed at all, and this checks that it
+    /// names the pending shot rather than a cell of the defender's choosing.
+    pub fn confirm_shot(&mut self, coords: Coords) -> StdResult<()> {
+        if self.state.next_shot != Some(coords) {
+            return Err(StdError::generic_err(
+                "You can only confirm the pending shot".to_string(),
+            ));
+        }
+
+        self.opponent_mut().pasture.shots.push(coords);
+        if let Some(ammo) = &mut self.player_mut().ammo {
+            *ammo = ammo.saturating_sub(1);
+        }
+        Ok(())
     }
 
+    /// The shots fired by the player whose turn it is
     pub fn get_player_shots(&self) -> Shots {
-        let pasture = &self.opponent().pasture;
-        let all_shots: &[Coords] = &pasture.shots;
-        let (hits, misses) = all_shots
+        self.opponent().pasture.incoming_shots()
+    }
+
+    /// The shots fired by the opponent of the player whose turn it is
+    pub fn get_opponent_shots(&self) -> Shots {
+        self.player().pasture.incoming_shots()
+    }
+
+    /// Record a player's commitment to their opening shot.
+    pub fn commit_opening(&mut self, credentials: &Credentials, hash: Binary) -> StdResult<()> {
+        let seat = self.seat_of(credentials)?;
+        let opening = self.state.opening.as_mut().ok_or_else(|| {
+            StdError::generic_err("This game has no pending opening salvo".to_string())
+        })?;
+
+        opening.commitments[seat] = Some(hash);
+
+        Ok(())
+    }
+
+    /// Reveal a player's opening shot, and check it against their commitment.
+    ///
+    /// Once both shots are revealed they are resolved together, and regular turns begin.
+    pub fn reveal_opening(
+        &mut self,
+        credentials: &Credentials,
+        coords: Coords,
+        salt: &str,
+        height: u64,
+    ) -> StdResult<()> {
+        let seat = self.seat_of(credentials)?;
+        let opening = self.state.opening.as_mut().ok_or_else(|| {
+            StdError::generic_err("This game has no pending opening salvo".to_string())
+        })?;
+
+        let commitment = match (&opening.commitments[seat], &opening.commitments[1 - seat]) {
+            (Some(commitment), Some(_)) => commitment,
+            _ => {
+                return Err(StdError::generic_err(
+                    "Both players must commit to an opening shot before revealing".to_string(),
+                ))
+            }
+        };
+        if shot_commitment(coords, salt).as_slice() != commitment.as_slice() {
+            return Err(StdError::generic_err(
+                "The opening shot does not match your commitment".to_string(),
+            ));
+        }
+        opening.reveals[seat] = Some(coords);
+
+        let reveals = opening.reveals;
+        if let [Some(first), Some(second)] = reveals {
+            self.state.opening = None;
+            self.state.players[1].pasture.shots.push(first);
+            self.state.players[0].pasture.shots.push(second);
+            self.start_turn(height);
+        }
+
+        Ok(())
+    }
+
+    /// Commit to the hash of the next shot, in games played with committed shots
+    pub fn commit_shot(&mut self, hash: Binary, height: u64) {
+        self.state.shot_commitment = Some(ShotCommitment { hash, height });
+    }
+
+    /// Reveal the committed shot, which makes it the pending shot.
+    ///
+    /// This has to happen in a later block than the commitment.
+    pub fn reveal_shot(&mut self, coords: Coords, salt: &str, height: u64) -> StdResult<()> {
+        let commitment = self.state.shot_commitment.as_ref().ok_or_else(|| {
+            StdError::generic_err("There is no committed shot to reveal".to_string())
+        })?;
+        if height <= commitment.height {
+            return Err(StdError::generic_err(
+                "The shot can only be revealed after the block it was committed in".to_string(),
+            ));
+        }
+        if shot_commitment(coords, salt).as_slice() != commitment.hash.as_slice() {
+            return Err(StdError::generic_err(
+                "The shot does not match your commitment".to_string(),
+            ));
+        }
+        self.state.shot_commitment = None;
+        self.shoot(coords);
+
+        Ok(())
+    }
+
+    /// Note an action of the player with these credentials, unless they already acted
+    /// within the last `cooldown` blocks.
+    pub fn record_action(
+        &mut self,
+        credentials: &Credentials,
+        height: u64,
+        cooldown: u64,
+    ) -> StdResult<()> {
+        let seat = self.seat_of(credentials)?;
+        let player = &mut self.state.players[seat];
+        if let Some(last_action) = player.last_action {
+            let next_action = last_action.saturating_add(cooldown);
+            if height < next_action {
+                return Err(StdError::generic_err(format!(
+                    "You can't act again before height {}",
+                    next_action
+                )));
+            }
+        }
+        player.last_action = Some(height);
+
+        Ok(())
+    }
+
+    /// In games played with sender authentication, reject moves that weren't sent from the
+    /// address the player with these credentials joined from
+    pub fn check_sender(&self, credentials: &Credentials, sender: &HumanAddr) -> StdResult<()> {
+        let seat = self.seat_of(credentials)?;
+        if self.state.rules.sender_auth && self.state.players[seat].address != *sender {
+            return Err(StdError::unauthorized());
+        }
+
+        Ok(())
+    }
+
+    fn seat_of(&self, credentials: &Credentials) -> StdResult<usize> {
+        self.state
+            .players
             .iter()
-            .partition(|shot| pasture.herds.iter().any(|herd| herd.is_at(**shot)));
+            .position(|player| player.matches_credentials(credentials))
+            .ok_or_else(StdError::unauthorized)
+    }
 
-        Shots { hits, misses }
+    /// Ask to take back the pending shot. The opponent has to grant this.
+    pub fn request_takeback(&mut self) {
+        self.state.takeback_requested = true;
     }
 
-    pub fn get_opponent_shots(&self) -> Shots {
-        let pasture = &self.player().pasture;
-        let all_shots: &[Coords] = &pasture.shots;
-        let (hits, misses) = all_shots
+    /// Take back the pending shot, if the shooter asked for it, without ending the turn.
+    pub fn grant_takeback(&mut self) {
+        self.state.takeback_requested = false;
+        self.state.next_shot = None;
+    }
+
+    /// Consent to let holders of `key` watch both pastures of the game, or withdraw consent
+    /// with `None`. A key only works once both players consented to it.
+    pub fn set_observer_key(
+        &mut self,
+        credentials: &Credentials,
+        key: Option<Password>,
+    ) -> StdResult<()> {
+        let seat = self.seat_of(credentials)?;
+        self.state.players[seat].observer_key = key;
+
+        Ok(())
+    }
+
+    /// The key both players consented to let observers use, if they agree on one
+    pub fn observer_key(&self) -> Option<&Password> {
+        match self.state.players.as_slice() {
+            [first, second] if first.observer_key == second.observer_key => {
+                first.observer_key.as_ref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the player whose turn it is has to place a reinforcement before shooting
+    pub fn reinforcement_due(&self) -> bool {
+        self.player().pasture.herds_placed() < self.herds_allowed()
+    }
+
+    /// Place a reinforcement that is due for the player whose turn it is
+    pub fn reinforce(&mut self, herd: Herd) -> StdResult<()> {
+        let board = self.state.board.clone();
+        self.player_mut().pasture.reinforce(herd, &board)
+    }
+
+    /// Resolve a confirmed shot, which was a hit or not.
+    ///
+    /// The game ends if the shot cleared the opponent's pasture. Otherwise the player shoots
+    /// again if the rules allow it, or the turn passes to the opponent.
+    pub fn resolve_shot(&mut self, hit: bool, height: u64) {
+        if self.reaches_sudden_death() {
+            self.enter_sudden_death();
+        }
+
+        let shoot_again = hit && self.state.rules.shoot_again_on_hit;
+        let bonus_shot = !shoot_again
+            && self.state.sudden_death
+            && self.state.rules.sudden_death == Some(SuddenDeath::DoubleShots)
+            && !self.state.bonus_shot_taken;
+        // Rounds end with the turn of the second player
+        let round_over = !shoot_again && !bonus_shot && self.state.turn == 1;
+        if round_over {
+            self.state.rounds += 1;
+        }
+
+        if let Some(winner) = self.victor(round_over) {
+            self.finish(winner);
+        } else if shoot_again {
+            self.shoot_again(height);
+        } else if bonus_shot {
+            self.state.bonus_shot_taken = true;
+            self.shoot_again(height);
+        } else {
+            self.end_turn(height);
+        }
+    }
+
+    /// End the turn without a shot, in games that allow passing.
+    ///
+    /// Passing the last turn of a round can still decide the game.
+    pub fn pass(&mut self, height: u64) {
+        let round_over = self.state.turn == 1;
+        if round_over {
+            self.state.rounds += 1;
+        }
+
+        match self.victor(round_over) {
+            Some(winner) => self.finish(winner),
+            None => self.end_turn(height),
+        }
+    }
+
+    /// The player who won the game with the shot just resolved, if any, according to the
+    /// victory condition of the game. `round_over` tells whether the shot ended a round.
+    fn victor(&self, round_over: bool) -> Option<u8> {
+        let shooter = self.state.turn;
+        let target = (shooter + 1) % 2;
+        let pasture = |seat: u8| &self.state.players[usize::from(seat)].pasture;
+        // Players with reinforcements still to come aren't out yet
+        let fleet = self.state.board.herd_count();
+        let eliminated =
+            |seat: u8| pasture(seat).is_cleared() && pasture(seat).herds_placed() == fleet;
+        // Both pastures can only be cleared at once by revealing the outer ring in sudden
+        // death, and then the shooter got there first
+        if eliminated(target) {
+            return Some(shooter);
+        }
+        if eliminated(shooter) {
+            return Some(target);
+        }
+
+        let victory = self.state.rules.victory;
+        if let VictoryCondition::FirstToHits { hits } = victory {
+            let first = [shooter, target]
+                .iter()
+                .copied()
+                .find(|&seat| pasture((seat + 1) % 2).sheep_hit() >= hits);
+            if first.is_some() {
+                return first;
+            }
+        }
+
+        let out_of_ammo = self
+            .state
+            .players
             .iter()
-            .partition(|shot| pasture.herds.iter().any(|herd| herd.is_at(**shot)));
+            .all(|player| player.ammo == Some(0));
+        let scored = match victory {
+            VictoryCondition::PointsAfterRounds { rounds }
+            | VictoryCondition::SurviveRounds { rounds } => {
+                round_over && self.state.rounds >= rounds
+            }
+            _ => false,
+        };
+        if !out_of_ammo && !scored {
+            return None;
+        }
 
-        Shots { hits, misses }
+        let lead = match victory {
+            VictoryCondition::EliminateAll | VictoryCondition::FirstToHits { .. } => {
+                leader(pasture(1).sheep_hit(), pasture(0).sheep_hit())
+            }
+            VictoryCondition::PointsAfterRounds { .. } => {
+                leader(pasture(1).points_conceded(), pasture(0).points_conceded())
+            }
+            VictoryCondition::SurviveRounds { .. } => {
+                leader(pasture(0).sheep_left(), pasture(1).sheep_left())
+            }
+        };
+        // Tied scores are checked again after the next round, unless nobody can shoot anymore
+        match lead {
+            None if out_of_ammo => Some(self.tie_break()),
+            lead => lead,
+        }
+    }
+
+    /// Settle a tie between players who are both out of shots. The player who landed their
+    /// last hit in fewer shots wins, and if that's tied as well, the player who shot second.
+    fn tie_break(&self) -> u8 {
+        let pasture = |seat: usize| &self.state.players[seat].pasture;
+        match pasture(1)
+            .shots_to_last_hit()
+            .cmp(&pasture(0).shots_to_last_hit())
+        {
+            Ordering::Less => 0,
+            Ordering::Greater | Ordering::Equal => 1,
+        }
+    }
+
+    /// Whether the game should enter sudden death, because both players are down to
+    /// their last herd
+    fn reaches_sudden_death(&self) -> bool {
+        self.state.rules.sudden_death.is_some()
+            && !self.state.sudden_death
+            && self
+                .state
+                .players
+                .iter()
+                .all(|player| player.pasture.herds_left() == 1)
+    }
+
+    /// Start sudden death. Revealing the outer ring may clear a pasture, and decide the game.
+    fn enter_sudden_death(&mut self) {
+        self.state.sudden_death = true;
+        if self.state.rules.sudden_death != Some(SuddenDeath::OuterRing) {
+            return;
+        }
+
+        let size = self.state.board.size;
+        for player in self.state.players.iter_mut() {
+            player.pasture.reveal_outer_ring(size);
+        }
+    }
+
+    /// Let the player whose turn it is shoot again, after their shot was confirmed.
+    fn shoot_again(&mut self, height: u64) {
+        self.state.turns += 1;
+        self.state.next_shot = None;
+        self.state.takeback_requested = false;
+        self.start_turn(height);
+    }
+
+    /// End a game that expired undecided against the player holding it up, who forfeits it
+    /// to their opponent. Returns whether anyone did, which nobody has during an opening
+    /// salvo both players got equally far through.
+    pub fn forfeit_stalled(&mut self) -> bool {
+        match self.stalling_seat() {
+            Some(seat) => {
+                self.finish((seat + 1) % 2);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The seat of the player who owes the game its next action, if only one of them does
+    fn stalling_seat(&self) -> Option<u8> {
+        if let Some(opening) = &self.state.opening {
+            let progress = |seat: usize| match (&opening.commitments[seat], opening.reveals[seat]) {
+                (_, Some(_)) => 2,
+                (Some(_), None) => 1,
+                (None, None) => 0,
+            };
+            return match progress(0).cmp(&progress(1)) {
+                Ordering::Less => Some(0),
+                Ordering::Greater => Some(1),
+                Ordering::Equal => None,
+            };
+        }
+        // A pending shot waits on its confirmation, anything else on the player on turn
+        if self.state.next_shot.is_some() {
+            Some((self.state.turn + 1) % 2)
+        } else {
+            Some(self.state.turn)
+        }
+    }
+
+    /// End the game, with the player at this index as the winner.
+    fn finish(&mut self, winner: u8) {
+        self.state.turns += 1;
+        self.state.next_shot = None;
+        self.state.takeback_requested = false;
+        self.state.winner = Some(winner);
     }
 
     /// End the running turn.
     ///
-    /// This will always be called by the opponent of the current player, after confirming the shot.
-    pub fn end_turn(&mut self) {
+    /// This is called once the opponent of the current player confirms the shot, or when the
+    /// current player passes.
+    fn end_turn(&mut self, height: u64) {
+        self.state.turns += 1;
         self.state.next_shot = None;
+        self.state.takeback_requested = false;
+        self.state.bonus_shot_taken = false;
         self.state.turn = (self.state.turn + 1) % 2;
+        // A player who is out of shots sits out while the opponent still has some
+        if self.player().ammo == Some(0) && self.opponent().ammo != Some(0) {
+            self.state.turn = (self.state.turn + 1) % 2;
+        }
+        self.start_turn(height);
     }
 }
 
@@ -105,10 +910,246 @@ pub struct Game {
 }
 
 impl Game {
-    pub fn new(name: String) -> Self {
+    pub fn new(
+        name: String,
+        board: BoardConfig,
+        mut rules: GameRules,
+        callback: Option<Callback>,
+        expires_at: u64,
+    ) -> Self {
+        let opening = if rules.opening_salvo {
+            Some(OpeningSalvo::default())
+        } else {
+            None
+        };
+        rules.version = RULES_VERSION;
+        Self {
+            name,
+            state: GameState {
+                board,
+                rules,
+                opening,
+                callback,
+                expires_at: Some(expires_at),
+                version: STATE_VERSION,
+                ..GameState::default()
+            },
+        }
+    }
+
+    /// A practice game on the classic board, waiting for its player and the tutor to join
+    pub fn new_practice(name: String, expires_at: u64) -> Self {
         Self {
             name,
-            state: GameState::default(),
+            state: GameState {
+                rules: GameRules {
+                    version: RULES_VERSION,
+                    ..GameRules::default()
+                },
+                expires_at: Some(expires_at),
+                practice: true,
+                version: STATE_VERSION,
+                ..GameState::default()
+            },
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this is a practice game against the scripted tutor
+    pub fn is_practice(&self) -> bool {
+        self.state.practice
+    }
+
+    /// The number of turns resolved so far
+    pub fn turns(&self) -> u32 {
+        self.state.turns
+    }
+
+    /// The amount of herds each player may have placed by now. Games with reinforcements
+    /// start with half of the fleet, rounded up, and allow another herd every
+    /// `REINFORCEMENT_INTERVAL` turns.
+    pub fn herds_allowed(&self) -> u32 {
+        let fleet = self.state.board.herd_count();
+        if !self.state.rules.reinforcements {
+            return fleet;
+        }
+
+        let reinforcements = self.state.turns / REINFORCEMENT_INTERVAL;
+        (fleet - fleet / 2)
+            .saturating_add(reinforcements)
+            .min(fleet)
+    }
+
+    /// Whether the opening salvo still has to be resolved before turns can start
+    pub fn in_opening(&self) -> bool {
+        self.state.opening.is_some()
+    }
+
+    /// Hold a deposit until the game starts
+    pub fn hold_deposit(&mut self, deposit: Deposit) {
+        self.state.deposit = Some(deposit);
+    }
+
+    /// The deposit held by the game, if any
+    pub fn deposit(&self) -> Option<&Deposit> {
+        self.state.deposit.as_ref()
+    }
+
+    /// Release the deposit held by the game, so it can be paid out
+    pub fn take_deposit(&mut self) -> Option<Deposit> {
+        self.state.deposit.take()
+    }
+
+    /// Play the game for a wager, which each player has to stake when joining
+    pub fn set_wager(&mut self, wager: Coin) {
+        self.state.wager = Some(wager);
+    }
+
+    /// The stake each player puts up, if the game is played for a wager
+    pub fn wager(&self) -> Option<&Coin> {
+        self.state.wager.as_ref()
+    }
+
+    /// Hold a player's stake until the game is decided
+    pub fn hold_stake(&mut self, stake: Deposit) {
+        self.state.stakes.push(stake);
+    }
+
+    /// Release the stakes held by the game, so they can be refunded
+    pub fn take_stakes(&mut self) -> Vec<Deposit> {
+        std::mem::take(&mut self.state.stakes)
+    }
+
+    /// Release the stakes held by the game as a single pot, so it can be paid to the winner
+    pub fn take_pot(&mut self) -> Option<Coin> {
+        let denom = self.state.stakes.first()?.amount.denom.clone();
+        let amount = self
+            .take_stakes()
+            .iter()
+            .map(|stake| stake.amount.amount.u128())
+            .sum();
+        Some(Coin {
+            denom,
+            amount: Uint128(amount),
+        })
+    }
+
+    /// Whether the game expired at the given height
+    pub fn is_expired(&self, height: u64) -> bool {
+        match self.state.expires_at {
+            Some(expires_at) => height >= expires_at,
+            None => false,
+        }
+    }
+
+    /// The amount of players who joined the game
+    pub fn player_count(&self) -> u32 {
+        self.state.players.len() as u32
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.state.players
+    }
+
+    /// The player whose turn it is to shoot, once they joined
+    pub fn shooter(&self) -> Option<&Player> {
+        self.state.players.get(usize::from(self.state.turn))
+    }
+
+    /// The player who won the game, once it's over
+    pub fn winner(&self) -> Option<&Player> {
+        self.state
+            .winner
+            .map(|winner| &self.state.players[usize::from(winner)])
+    }
+
+    pub fn locale(&self) -> Option<&str> {
+        self.state.locale.as_deref()
+    }
+
+    pub fn set_locale(&mut self, locale: String) {
+        self.state.locale = Some(locale);
+    }
+
+    /// The statistics of the game, once it's over. `height` is the block it ended at.
+    pub fn stats(&self, height: u64) -> Option<GameStats> {
+        let winner = self.state.winner?;
+        let side = |seat: u8| {
+            let player = &self.state.players[usize::from(seat)];
+            let target = &self.state.players[usize::from((seat + 1) % 2)];
+            let Shots { hits, misses } = target.pasture.incoming_shots();
+            let (hits, shots) = (hits.len() as u32, (hits.len() + misses.len()) as u32);
+            PlayerStats {
+                username: player.username.to_string(),
+                shots,
+                hits,
+                accuracy: (hits * 10_000).checked_div(shots).unwrap_or(0),
+            }
+        };
+
+        Some(GameStats {
+            duration: self
+                .state
+                .started_at
+                .map(|started_at| height.saturating_sub(started_at)),
+            winner: side(winner),
+            loser: side((winner + 1) % 2),
+        })
+    }
+
+    /// The player who lost the game, once it's over
+    pub fn loser(&self) -> Option<&Player> {
+        self.state
+            .winner
+            .map(|winner| &self.state.players[usize::from((winner + 1) % 2)])
+    }
+
+    /// Whether both players have joined the game
+    pub fn is_full(&self) -> bool {
+        self.state.players.len() == 2
+    }
+
+    /// Note that both players are in, and the game started
+    pub fn start(&mut self, height: u64) {
+        self.state.started_at = Some(height);
+    }
+
+    /// Start the clock of the running turn
+    pub fn start_turn(&mut self, height: u64) {
+        self.state.turn_started_at = height;
+    }
+
+    /// The block height by which the running turn should be over, if the game has a turn clock
+    pub fn turn_deadline(&self) -> Option<u64> {
+        self.state
+            .rules
+            .turn_clock
+            .map(|blocks| self.state.turn_started_at.saturating_add(blocks))
+    }
+
+    pub fn board(&self) -> &BoardConfig {
+        &self.state.board
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.state.rules
+    }
+
+    pub fn callback(&self) -> Option<&Callback> {
+        self.state.callback.as_ref()
+    }
+
+    /// Fail if the game has expired by the given block height
+    pub fn check_expiry(&self, height: u64) -> StdResult<()> {
+        match self.state.expires_at {
+            Some(expires_at) if self.is_expired(height) => Err(StdError::generic_err(format!(
+                "Game named {:?} expired at height {}",
+                self.name, expires_at
+            ))),
+            _ => Ok(()),
         }
     }
 
@@ -122,13 +1163,17 @@ impl Game {
     }
 
     pub fn save<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
-        singleton(&mut prefixed(GAMES, storage), self.name.as_bytes()).save(&self.state)
+        storage::save_game(storage, &self.name, &self.state)
+    }
+
+    pub fn remove<S: Storage>(&self, storage: &mut S) {
+        storage::remove_game(storage, &self.name);
     }
 
     pub fn load<S: Storage>(storage: &S, name: String) -> StdResult<Self> {
-        let state = singleton_read(&prefixed_read(GAMES, storage), name.as_bytes()).may_load()?;
+        let state = Self::may_load(storage, name.clone())?;
         if let Some(state) = state {
-            Ok(Self { name, state })
+            Ok(state)
         } else {
             Err(StdError::generic_err(format!(
                 "Game named {:?} doesn't exist",
@@ -138,25 +1183,57 @@ impl Game {
     }
 
     pub fn may_load<S: Storage>(storage: &S, name: String) -> StdResult<Option<Self>> {
-        singleton_read(&prefixed_read(GAMES, storage), name.as_bytes())
-            .may_load()
-            .map(|maybe| maybe.map(|state| Self { name, state }))
+        let mut state = match storage::load_game(storage, &name)? {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        if state.version < 2 {
+            let legacy: Option<LegacyState> = storage::load_legacy_game(storage, &name)?;
+            if let Some(legacy) = legacy {
+                state.legacy_shoot_again_on_hit = legacy.board.shoot_again_on_hit;
+            }
+        }
+        state.migrate();
+
+        Ok(Some(Self { name, state }))
     }
 
-    pub fn add_player(&mut self, player: Player) -> StdResult<()> {
+    pub fn add_player(&mut self, mut player: Player) -> StdResult<()> {
         if self.state.players.len() == 1 && self.state.players[0].username == player.username {
             return Err(StdError::generic_err(format!(
                 "username {} is already taken!",
                 player.username
             )));
         }
-        if self.state.players.len() > 2 {
+        if self.state.players.len() >= 2 {
             return Err(StdError::generic_err(String::from("Game already full!")));
         }
+        if let Some(first) = self.state.players.first() {
+            let same_address = !first.address.is_empty() && first.address == player.address;
+            if self.state.rules.distinct_players
+                && (first.password == player.password || same_address)
+            {
+                return Err(StdError::generic_err(
+                    "Both seats of this game can't be taken by the same player".to_string(),
+                ));
+            }
+        }
 
-        player.pasture.verify()?;
+        if self.state.rules.reinforcements {
+            let herds = self.herds_allowed();
+            if player.pasture.herds_placed() != herds {
+                return Err(StdError::generic_err(format!(
+                    "Start with {} herds. The rest of the fleet arrives as reinforcements",
+                    herds
+                )));
+            }
+            player.pasture.verify_partial(&self.state.board)?;
+        } else {
+            player.pasture.verify(&self.state.board)?;
+        }
         // TODO add minimum limit on password strength?
 
+        player.ammo = self.state.rules.ammo;
         self.state.players.push(player);
 
         Ok(())
@@ -167,25 +1244,209 @@ impl Game {
 pub struct GameState {
     /// The two players in the game
     players: Vec<Player>,
+    /// The size of the pasture and the herds placed in it
+    #[serde(default)]
+    board: BoardConfig,
+    /// The optional rules the game is played with
+    #[serde(default)]
+    rules: GameRules,
     /// The index of the next player to shoot. 0 or 1.
     turn: u8,
     /// The coordinate of the next shot. pending confirmation. None means no shot is pending confirmation.
     next_shot: Option<Coords>,
+    /// The opening salvo, if the game starts with one and it hasn't been resolved yet.
+    opening: Option<OpeningSalvo>,
+    /// The commitment to the next shot, until it's revealed
+    #[serde(default)]
+    shot_commitment: Option<ShotCommitment>,
+    /// The number of turns resolved so far
+    #[serde(default)]
+    turns: u32,
+    /// The number of rounds, in which both players had a turn, played so far
+    #[serde(default)]
+    rounds: u32,
+    /// A contract notified after every resolved turn
+    callback: Option<Callback>,
+    /// The block height from which the game no longer accepts any actions.
+    /// Games created before expiry was introduced never expire.
+    expires_at: Option<u64>,
+    /// The turn clock of states stored before version 2, which is part of `rules` since
+    #[serde(rename = "turn_clock", default, skip_serializing)]
+    legacy_turn_clock: Option<u64>,
+    /// The shoot-again rule of states stored before version 2, which kept it on the board
+    #[serde(skip)]
+    legacy_shoot_again_on_hit: bool,
+    /// The block height at which the running turn started
+    #[serde(default)]
+    turn_started_at: u64,
+    /// The block height at which the game started, for games started since it's recorded
+    #[serde(default)]
+    started_at: Option<u64>,
+    /// Whether the shooter asked to take back the pending shot
+    #[serde(default)]
+    takeback_requested: bool,
+    /// The index of the player who won the game, once it's over
+    #[serde(default)]
+    winner: Option<u8>,
+    /// Whether the game entered sudden death
+    #[serde(default)]
+    sudden_death: bool,
+    /// Whether the shooter already fired the extra shot of a sudden death turn
+    #[serde(default)]
+    bonus_shot_taken: bool,
+    /// The deposit paid to open the lobby, until it's refunded or collected
+    #[serde(default)]
+    deposit: Option<Deposit>,
+    /// The stake each player puts up, in games played for a wager
+    #[serde(default)]
+    wager: Option<Coin>,
+    /// The stakes paid by the players who joined, until they're paid out or refunded
+    #[serde(default)]
+    stakes: Vec<Deposit>,
+    /// The language the players are expected to speak
+    #[serde(default)]
+    locale: Option<String>,
+    /// Whether this is a practice game against the scripted tutor.
+    /// Practice games are left out of listings and statistics.
+    #[serde(default)]
+    practice: bool,
+    /// The layout version this state was stored with
+    #[serde(default)]
+    version: u8,
+}
+
+impl GameState {
+    /// Bring a state stored by an older version of the contract up to date.
+    ///
+    /// The result is only persisted the next time the game is saved.
+    fn migrate(&mut self) {
+        if self.version < 1 {
+            if let [first, second] = self.players.as_mut_slice() {
+                std::mem::swap(&mut first.pasture.shots, &mut second.pasture.shots);
+            }
+        }
+        if self.version < 2 {
+            self.rules = GameRules {
+                version: RULES_VERSION,
+                opening_salvo: self.opening.is_some(),
+                shoot_again_on_hit: self.legacy_shoot_again_on_hit,
+                turn_clock: self.legacy_turn_clock.take(),
+                sudden_death: None,
+                victory: VictoryCondition::EliminateAll,
+                distinct_players: false,
+                committed_shots: false,
+                reinforcements: false,
+                sender_auth: false,
+                passing: false,
+                ammo: None,
+                strict_confirms: false,
+            };
+        }
+        self.version = STATE_VERSION;
+    }
+}
+
+/// The parts of a state stored before version 2 that later layouts moved elsewhere
+#[derive(Serialize, Deserialize)]
+struct LegacyState {
+    #[serde(default)]
+    board: LegacyBoard,
+}
+
+/// A board stored before version 2, which also held the shoot-again rule
+#[derive(Serialize, Deserialize, Default)]
+struct LegacyBoard {
+    #[serde(default)]
+    shoot_again_on_hit: bool,
+}
+
+/// Funds held while a game waits for its players
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Deposit {
+    /// The address that paid the deposit
+    pub owner: HumanAddr,
+    pub amount: Coin,
+}
+
+/// An opening where both players fire a shot blindly, before turn order starts.
+///
+/// Each player first commits to the hash of their shot, and only once both commitments
+/// are in, the shots are revealed. This way neither player can react to the other's shot,
+/// which removes the first-move advantage.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct OpeningSalvo {
+    /// Commitments to the opening shots, indexed like `GameState::players`
+    commitments: [Option<Binary>; 2],
+    /// Revealed opening shots, indexed like `GameState::players`
+    reveals: [Option<Coords>; 2],
+}
+
+/// A shot committed to by its hash, in games played with committed shots
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShotCommitment {
+    hash: Binary,
+    /// The block height the commitment was made at
+    height: u64,
+}
+
+/// The seat of the player with the higher score, or `None` if the scores are tied
+fn leader(first: u32, second: u32) -> Option<u8> {
+    match first.cmp(&second) {
+        Ordering::Greater => Some(0),
+        Ordering::Less => Some(1),
+        Ordering::Equal => None,
+    }
+}
+
+/// The commitment expected for an opening or committed shot: `sha256(x || y || salt)`
+pub fn shot_commitment(coords: Coords, salt: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update([coords.x, coords.y]);
+    hasher.update(salt.as_bytes());
+    hasher.finalize().to_vec()
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
 pub struct Player {
+    /// Kept as a plain string, since games stored before usernames and passwords were
+    /// bounded may hold longer ones
     username: String,
     password: String,
     pasture: Pasture,
+    /// The address that joined the game as this player.
+    /// Empty for the tutor, and for players who joined before addresses were recorded.
+    #[serde(default)]
+    address: HumanAddr,
+    /// The block height of the last action this player took in the game
+    #[serde(default)]
+    last_action: Option<u64>,
+    /// The key this player consented to let observers watch the game with
+    #[serde(default)]
+    observer_key: Option<Password>,
+    /// The public comment the player attached to their latest shot
+    #[serde(default)]
+    comment: Option<Comment>,
+    /// The shots the player has left, in games with limited ammunition
+    #[serde(default)]
+    ammo: Option<u32>,
 }
 
 impl Player {
-    pub fn new(username: String, password: String, pasture: Pasture) -> Self {
+    pub fn new(
+        username: Username,
+        password: Password,
+        pasture: Pasture,
+        address: HumanAddr,
+    ) -> Self {
         Self {
-            username,
-            password,
+            username: username.into(),
+            password: password.into(),
             pasture,
+            address,
+            last_action: None,
+            observer_key: None,
+            comment: None,
+            ammo: None,
         }
     }
 
@@ -193,94 +1454,362 @@ impl Player {
         &self.username
     }
 
+    pub fn address(&self) -> &HumanAddr {
+        &self.address
+    }
+
     // TODO make this more constant time to prevent side-channel attacks on the credentials
     pub fn matches_credentials(&self, credentials: &Credentials) -> bool {
-        self.username == credentials.username && self.password == credentials.password
+        self.username == credentials.username.as_str()
+            && self.password == credentials.password.as_str()
     }
 
-    pub fn pasture(&self, credentials: &Credentials) -> Option<&Pasture> {
-        if !self.matches_credentials(credentials) {
-            None
-        } else {
-            Some(&self.pasture)
-        }
+    pub fn pasture(&self) -> &Pasture {
+        &self.pasture
+    }
+
+    /// The block height of the last action this player took in the game, if any
+    pub fn last_action(&self) -> Option<u64> {
+        self.last_action
+    }
+
+    /// The public comment the player attached to their latest shot
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn ammo(&self) -> Option<u32> {
+        self.ammo
     }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
 pub struct Pasture {
     herds: Vec<Herd>,
+    /// Shots fired at this pasture
     shots: Vec<Coords>,
 }
 
-fn expected_herd_count_of_length(length: u8) -> u32 {
-    match length {
-        2 => 1,
-        3 => 2,
-        4 => 1,
-        5 => 1,
-        _ => 0,
-    }
-}
-
 impl Pasture {
     pub fn new(herds: Vec<Herd>, shots: Vec<Coords>) -> Self {
         Self { herds, shots }
     }
 
-    fn verify(&self) -> StdResult<()> {
+    pub fn herds(&self) -> &[Herd] {
+        &self.herds
+    }
+
+    /// The shots fired at this pasture, in the order they were fired
+    pub fn shots(&self) -> &[Coords] {
+        &self.shots
+    }
+
+    /// The amount of shots it took to land the last hit on this pasture, or 0 if none hit
+    pub fn shots_to_last_hit(&self) -> usize {
+        self.shots
+            .iter()
+            .rposition(|&shot| self.is_hit(shot))
+            .map_or(0, |index| index + 1)
+    }
+
+    /// Whether a shot at these coordinates hits one of the herds in this pasture
+    pub fn is_hit(&self, coords: Coords) -> bool {
+        self.herds.iter().any(|herd| herd.is_at(coords))
+    }
+
+    /// The confirmed shot at these coordinates, if the cell was shot
+    pub fn shot_at(&self, coords: Coords) -> Option<ShotRecord> {
+        let index = self.shots.iter().position(|shot| *shot == coords)?;
+        let state = match self.herds.iter().find(|herd| herd.is_at(coords)) {
+            Some(herd) if self.is_sunk(herd) => CellState::Sunk,
+            Some(_) => CellState::Hit,
+            None => CellState::Miss,
+        };
+        Some(ShotRecord {
+            number: index as u32 + 1,
+            state,
+        })
+    }
+
+    /// The state of a cell, as the owner of this pasture sees it
+    pub fn own_cell(&self, coords: Coords) -> CellState {
+        let shot = self.shots.contains(&coords);
+        match self.herds.iter().find(|herd| herd.is_at(coords)) {
+            Some(herd) if self.is_sunk(herd) => CellState::Sunk,
+            Some(_) if shot => CellState::OwnSheepHit,
+            Some(_) => CellState::OwnSheep,
+            None if shot => CellState::Miss,
+            None => CellState::Unknown,
+        }
+    }
+
+    /// Describe this pasture cell by cell, as seen by its owner
+    pub fn view(&self, size: u8) -> MyPastureResponse {
+        let cells = (0..size)
+            .map(|y| (0..size).map(|x| self.own_cell(Coords { x, y })).collect())
+            .collect();
+
+        MyPastureResponse {
+            size,
+            herds: self.herds.clone(),
+            cells,
+        }
+    }
+
+    /// Whether every sheep in this pasture has been shot
+    pub fn is_cleared(&self) -> bool {
+        self.herds.iter().all(|herd| self.is_sunk(herd))
+    }
+
+    /// The amount of herds in this pasture that had all of their sheep shot
+    pub fn sunk_herds(&self) -> u32 {
+        self.herds.iter().filter(|herd| self.is_sunk(herd)).count() as u32
+    }
+
+    /// The amount of herds in this pasture that still have sheep left
+    pub fn herds_left(&self) -> u32 {
+        self.herds.iter().filter(|herd| !self.is_sunk(herd)).count() as u32
+    }
+
+    /// The amount of sheep in this pasture that have been shot
+    pub fn sheep_hit(&self) -> u32 {
+        self.herds
+            .iter()
+            .flat_map(Herd::sheep)
+            .filter(|sheep| self.shots.contains(sheep))
+            .count() as u32
+    }
+
+    /// The amount of sheep in this pasture that haven't been shot
+    pub fn sheep_left(&self) -> u32 {
+        let sheep: u32 = self.herds.iter().map(|herd| u32::from(herd.length)).sum();
+        sheep - self.sheep_hit()
+    }
+
+    /// The points scored by shooting at this pasture, in games decided by points
+    pub fn points_conceded(&self) -> u32 {
+        self.sheep_hit() * HIT_POINTS + self.sunk_herds() * SINK_BONUS
+    }
+
+    /// Count every cell on the edge of a pasture of this size as shot
+    fn reveal_outer_ring(&mut self, size: u8) {
+        let last = size.saturating_sub(1);
+        for y in 0..size {
+            for x in 0..size {
+                let coords = Coords::new(x, y);
+                let on_edge = x == 0 || y == 0 || x == last || y == last;
+                if on_edge && !self.shots.contains(&coords) {
+                    self.shots.push(coords);
+                }
+            }
+        }
+    }
+
+    /// The herds in this pasture that still have sheep left, counted by length
+    pub fn remaining_fleet(&self) -> Vec<FleetEntry> {
+        let mut fleet: Vec<FleetEntry> = vec![];
+        for herd in self.herds.iter().filter(|herd| !self.is_sunk(herd)) {
+            match fleet.iter_mut().find(|entry| entry.length == herd.length) {
+                Some(entry) => entry.count += 1,
+                None => fleet.push(FleetEntry {
+                    length: herd.length,
+                    count: 1,
+                }),
+            }
+        }
+        fleet.sort_unstable_by_key(|entry| entry.length);
+        fleet
+    }
+
+    /// The sheep of every herd in this pasture that was sunk
+    pub fn sunk_sheep(&self) -> Vec<Coords> {
+        self.herds
+            .iter()
+            .filter(|herd| self.is_sunk(herd))
+            .flat_map(Herd::sheep)
+            .collect()
+    }
+
+    fn is_sunk(&self, herd: &Herd) -> bool {
+        herd.sheep().all(|sheep| self.shots.contains(&sheep))
+    }
+
+    /// The shots fired at this pasture, split into hits and misses
+    pub fn incoming_shots(&self) -> Shots {
+        let (hits, misses) = self.shots.iter().partition(|shot| self.is_hit(**shot));
+
+        Shots { hits, misses }
+    }
+
+    fn verify(&self, board: &BoardConfig) -> StdResult<()> {
+        reject_violations(&self.violations(board))
+    }
+
+    /// Check a pasture that holds only part of its fleet, because the rest arrives as
+    /// reinforcements
+    fn verify_partial(&self, board: &BoardConfig) -> StdResult<()> {
+        let violations: Vec<_> = self
+            .violations(board)
+            .into_iter()
+            .filter(|violation| !matches!(violation, PlacementViolation::TooFewHerds { .. }))
+            .collect();
+        reject_violations(&violations)
+    }
+
+    /// The amount of herds placed in this pasture, including sunk ones
+    pub fn herds_placed(&self) -> u32 {
+        self.herds.len() as u32
+    }
+
+    /// Place a herd that arrived as a reinforcement. It has to fit the fleet and the
+    /// pasture like any other herd, and can't cover a cell that was already shot.
+    fn reinforce(&mut self, herd: Herd, board: &BoardConfig) -> StdResult<()> {
+        if self.shots.iter().any(|shot| herd.is_at(*shot)) {
+            return Err(StdError::generic_err(
+                "Reinforcements can't be placed on cells that were already shot".to_string(),
+            ));
+        }
+
+        let mut herds = self.herds.clone();
+        herds.push(herd);
+        let reinforced = Pasture::new(herds, vec![]);
+        reinforced.verify_partial(board)?;
+        self.herds = reinforced.herds;
+
+        Ok(())
+    }
+
+    /// Every way in which this pasture breaks the placement rules of the board
+    pub fn violations(&self, board: &BoardConfig) -> Vec<PlacementViolation> {
+        let mut violations = vec![];
+        if !self.shots.is_empty() {
+            violations.push(PlacementViolation::ShotsPresent);
+        }
+
         // Check that the amount of herds is correct
         // this is a mapping of herd length to count of herds with that length
         let mut herds = HashMap::<u8, u32>::new();
+        // Herds that are placed within the pasture, and can be checked for collisions
+        let mut placed = vec![];
 
-        for herd in self.herds.iter() {
-            herd.verify()?;
+        for (index, herd) in self.herds.iter().enumerate() {
+            match herd.violation(index, board.size) {
+                Some(violation) => violations.push(violation),
+                None => placed.push((index, herd)),
+            }
             herds
                 .entry(herd.length)
                 .and_modify(|count| count.add_assign(1_u32))
                 .or_insert(1);
         }
+        // Lengths that the fleet requires must be checked even if no herd has them
+        for entry in board.fleet.iter() {
+            herds.entry(entry.length).or_insert(0);
+        }
 
-        for (length, count) in herds.into_iter() {
-            let expected_count = expected_herd_count_of_length(length);
-            if expected_count > count {
-                return Err(StdError::generic_err(format!(
-                    "Too many herds of length {}. You should only have {} but you have {}",
-                    length, expected_count, count
-                )));
+        let mut herds: Vec<(u8, u32)> = herds.into_iter().collect();
+        herds.sort_unstable();
+        for (length, found) in herds {
+            let expected = board.expected_count(length);
+            if found > expected {
+                violations.push(PlacementViolation::TooManyHerds {
+                    length,
+                    expected,
+                    found,
+                });
             }
-            if expected_count < count {
-                return Err(StdError::generic_err(format!(
-                    "You need {} herds of length {}. Found only {}",
-                    count, length, expected_count
-                )));
+            if found < expected {
+                violations.push(PlacementViolation::TooFewHerds {
+                    length,
+                    expected,
+                    found,
+                });
             }
         }
 
         // Check that herds do not collide
-        for (index_1, herd_1) in self.herds.iter().enumerate() {
-            for (index_2, herd_2) in self.herds.iter().enumerate() {
-                if index_1 == index_2 {
-                    continue;
-                }
+        for (position, &(first, herd_1)) in placed.iter().enumerate() {
+            for &(second, herd_2) in placed.iter().skip(position + 1) {
                 if herd_1.intersects(herd_2) {
-                    return Err(StdError::generic_err(format!(
-                        "Herd {} from {} to {} intersects with herd {} from {} to {}",
-                        index_1,
-                        herd_1.coords,
-                        herd_1.end(),
-                        index_2,
-                        herd_2.coords,
-                        herd_2.end()
-                    )));
+                    violations.push(PlacementViolation::Overlap {
+                        first,
+                        first_start: herd_1.coords,
+                        first_end: herd_1.end(),
+                        second,
+                        second_start: herd_2.coords,
+                        second_end: herd_2.end(),
+                    });
                 }
             }
         }
 
-        Ok(())
+        violations
     }
 }
 
+/// Fail with every placement violation found, if there are any
+fn reject_violations(violations: &[PlacementViolation]) -> StdResult<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+    Err(StdError::generic_err(messages.join(". ")))
+}
+
+/// A way in which a pasture breaks the placement rules of its board.
+///
+/// Herds are referred to by their index in the pasture.
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlacementViolation {
+    #[display(fmt = "A new pasture can't have any shots in it")]
+    ShotsPresent,
+    #[display(fmt = "Herd {} at {} has no sheep", herd, coords)]
+    EmptyHerd { herd: usize, coords: Coords },
+    #[display(fmt = "Herd {} at {} isn't contained in the pasture", herd, coords)]
+    OutOfBounds { herd: usize, coords: Coords },
+    #[display(
+        fmt = "Too many herds of length {}. You should only have {} but you have {}",
+        length,
+        expected,
+        found
+    )]
+    TooManyHerds {
+        length: u8,
+        expected: u32,
+        found: u32,
+    },
+    #[display(
+        fmt = "You need {} herds of length {}. Found only {}",
+        expected,
+        length,
+        found
+    )]
+    TooFewHerds {
+        length: u8,
+        expected: u32,
+        found: u32,
+    },
+    #[display(
+        fmt = "Herd {} from {} to {} intersects with herd {} from {} to {}",
+        first,
+        first_start,
+        first_end,
+        second,
+        second_start,
+        second_end
+    )]
+    Overlap {
+        first: usize,
+        first_start: Coords,
+        first_end: Coords,
+        second: usize,
+        second_start: Coords,
+        second_end: Coords,
+    },
+}
+
 /// A group of sheep
 ///
 /// This represents a line of sheep following each other.
@@ -325,6 +1854,20 @@ impl Herd {
             && ranges_intersect(self.coords.y, self_end.y, other.coords.y, other_end.y)
     }
 
+    /// The location of every sheep in the herd
+    fn sheep(&self) -> impl Iterator<Item = Coords> + '_ {
+        (0..self.length).map(move |offset| match self.orientation {
+            Orientation::Horizontal => Coords {
+                x: self.coords.x.saturating_add(offset),
+                y: self.coords.y,
+            },
+            Orientation::Vertical => Coords {
+                x: self.coords.x,
+                y: self.coords.y.saturating_add(offset),
+            },
+        })
+    }
+
     /// location of last sheep
     fn end(&self) -> Coords {
         match self.orientation {
@@ -339,22 +1882,22 @@ impl Herd {
         }
     }
 
-    fn verify(&self) -> StdResult<()> {
+    fn violation(&self, index: usize, pasture_size: u8) -> Option<PlacementViolation> {
         if self.length == 0 {
-            return Err(StdError::generic_err(format!(
-                "Herd at {} has no sheep",
-                self.coords,
-            )));
+            return Some(PlacementViolation::EmptyHerd {
+                herd: index,
+                coords: self.coords,
+            });
         }
         let end = self.end();
-        if end.x >= PASTURE_SIZE || end.y >= PASTURE_SIZE {
-            return Err(StdError::generic_err(format!(
-                "Herd at {} isn't contained in the pasture",
-                self.coords,
-            )));
+        if end.x >= pasture_size || end.y >= pasture_size {
+            return Some(PlacementViolation::OutOfBounds {
+                herd: index,
+                coords: self.coords,
+            });
         }
 
-        Ok(())
+        None
     }
 }
 
@@ -375,6 +1918,17 @@ pub struct Coords {
     y: u8,
 }
 
+impl Coords {
+    pub fn new(x: u8, y: u8) -> Self {
+        Self { x, y }
+    }
+
+    /// Whether these coordinates lie within a pasture of this size
+    pub fn is_within(&self, size: u8) -> bool {
+        self.x < size && self.y < size
+    }
+}
+
 /// Orientation of a herd
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
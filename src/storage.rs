@@ -0,0 +1,453 @@
+//! Typed access to everything the contract keeps in storage.
+//!
+//! Every namespace is declared here, once, so new kinds of state can't reuse the keys of
+//! existing ones. The key schemes are those the data was always stored under.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{ReadonlyStorage, StdResult, Storage};
+use cosmwasm_storage::{
+    bucket, bucket_read, prefixed, prefixed_read, singleton, singleton_read, Bucket,
+    ReadonlyBucket, ReadonlySingleton, Singleton,
+};
+
+use crate::archive::ArchivedGame;
+use crate::records::HeadToHead;
+use crate::state::{Config, GameState, Template};
+use crate::treasury::{Sponsorship, Treasury};
+
+const CONFIG: &[u8] = b"config";
+const GAMES: &[u8] = b"games";
+const GAME_LIST: &[u8] = b"game_list";
+const GAME_LIST_SIZE: &[u8] = b"game_list_size";
+const GAME_LIST_INDEX: &[u8] = b"game_list_index";
+const TEMPLATES: &[u8] = b"templates";
+const TEMPLATE_LIST: &[u8] = b"template_list";
+const TEMPLATE_LIST_SIZE: &[u8] = b"template_list_size";
+const TEMPLATE_LIST_INDEX: &[u8] = b"template_list_index";
+const ARCHIVE: &[u8] = b"archive";
+const ARCHIVE_SIZE: &[u8] = b"archive_size";
+const ARCHIVE_INDEX: &[u8] = b"archive_index";
+const HEAD_TO_HEAD: &[u8] = b"head_to_head";
+const TREASURY: &[u8] = b"treasury";
+const SPONSORSHIPS: &[u8] = b"sponsorships";
+
+/// The amount of entries list queries return when no limit is given
+pub const DEFAULT_LIMIT: u32 = 10;
+/// The largest amount of entries list queries can return at once
+pub const MAX_LIMIT: u32 = 30;
+
+pub fn config<S: Storage>(storage: &mut S) -> Singleton<'_, S, Config> {
+    singleton(storage, CONFIG)
+}
+
+pub fn config_read<S: Storage>(storage: &S) -> ReadonlySingleton<'_, S, Config> {
+    singleton_read(storage, CONFIG)
+}
+
+/// Save the state of the game with this name
+pub fn save_game<S: Storage>(storage: &mut S, name: &str, state: &GameState) -> StdResult<()> {
+    singleton(&mut prefixed(GAMES, storage), name.as_bytes()).save(state)
+}
+
+/// Load the state of the game with this name, as it was stored
+pub fn load_game<S: Storage>(storage: &S, name: &str) -> StdResult<Option<GameState>> {
+    singleton_read(&prefixed_read(GAMES, storage), name.as_bytes()).may_load()
+}
+
+/// Load the state of the game with this name as an older layout, to recover what the
+/// current layout has no place for
+pub fn load_legacy_game<S: Storage, T: Serialize + DeserializeOwned>(
+    storage: &S,
+    name: &str,
+) -> StdResult<Option<T>> {
+    singleton_read(&prefixed_read(GAMES, storage), name.as_bytes()).may_load()
+}
+
+pub fn remove_game<S: Storage>(storage: &mut S, name: &str) {
+    singleton::<_, GameState>(&mut prefixed(GAMES, storage), name.as_bytes()).remove();
+}
+
+/// The names of listed games, keyed by the big-endian index they were listed under.
+/// Removed games leave a gap.
+pub fn game_list<S: Storage>(storage: &mut S) -> Bucket<'_, S, String> {
+    bucket(GAME_LIST, storage)
+}
+
+pub fn game_list_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, String> {
+    bucket_read(GAME_LIST, storage)
+}
+
+/// The number of games ever listed, including removed ones
+pub fn game_list_size<S: Storage>(storage: &mut S) -> Singleton<'_, S, u32> {
+    singleton(storage, GAME_LIST_SIZE)
+}
+
+pub fn game_list_size_read<S: Storage>(storage: &S) -> ReadonlySingleton<'_, S, u32> {
+    singleton_read(storage, GAME_LIST_SIZE)
+}
+
+/// The index each listed game is listed under, keyed by its name
+pub fn game_list_index<S: Storage>(storage: &mut S) -> Bucket<'_, S, u32> {
+    bucket(GAME_LIST_INDEX, storage)
+}
+
+pub fn game_list_index_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, u32> {
+    bucket_read(GAME_LIST_INDEX, storage)
+}
+
+/// Templates, keyed by name
+pub fn templates<S: Storage>(storage: &mut S) -> Bucket<'_, S, Template> {
+    bucket(TEMPLATES, storage)
+}
+
+pub fn templates_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, Template> {
+    bucket_read(TEMPLATES, storage)
+}
+
+/// The names of saved templates, keyed by the big-endian index they were listed under.
+/// Deleted templates leave a gap.
+pub fn template_list<S: Storage>(storage: &mut S) -> Bucket<'_, S, String> {
+    bucket(TEMPLATE_LIST, storage)
+}
+
+pub fn template_list_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, String> {
+    bucket_read(TEMPLATE_LIST, storage)
+}
+
+/// The number of templates ever listed, including deleted ones
+pub fn template_list_size<S: Storage>(storage: &mut S) -> Singleton<'_, S, u32> {
+    singleton(storage, TEMPLATE_LIST_SIZE)
+}
+
+pub fn template_list_size_read<S: Storage>(storage: &S) -> ReadonlySingleton<'_, S, u32> {
+    singleton_read(storage, TEMPLATE_LIST_SIZE)
+}
+
+/// The index each saved template is listed under, keyed by its name
+pub fn template_list_index<S: Storage>(storage: &mut S) -> Bucket<'_, S, u32> {
+    bucket(TEMPLATE_LIST_INDEX, storage)
+}
+
+pub fn template_list_index_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, u32> {
+    bucket_read(TEMPLATE_LIST_INDEX, storage)
+}
+
+/// Archived games, keyed by their big-endian index
+pub fn archive<S: Storage>(storage: &mut S) -> Bucket<'_, S, ArchivedGame> {
+    bucket(ARCHIVE, storage)
+}
+
+pub fn archive_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, ArchivedGame> {
+    bucket_read(ARCHIVE, storage)
+}
+
+/// The number of archived games
+pub fn archive_size<S: Storage>(storage: &mut S) -> Singleton<'_, S, u32> {
+    singleton(storage, ARCHIVE_SIZE)
+}
+
+pub fn archive_size_read<S: Storage>(storage: &S) -> ReadonlySingleton<'_, S, u32> {
+    singleton_read(storage, ARCHIVE_SIZE)
+}
+
+/// The index of the latest archived game with each name, keyed by the name
+pub fn archive_index<S: Storage>(storage: &mut S) -> Bucket<'_, S, u32> {
+    bucket(ARCHIVE_INDEX, storage)
+}
+
+pub fn archive_index_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, u32> {
+    bucket_read(ARCHIVE_INDEX, storage)
+}
+
+/// Head-to-head scores, keyed by the pair of players they are between
+pub fn head_to_head<S: Storage>(storage: &mut S) -> Bucket<'_, S, HeadToHead> {
+    bucket(HEAD_TO_HEAD, storage)
+}
+
+pub fn head_to_head_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, HeadToHead> {
+    bucket_read(HEAD_TO_HEAD, storage)
+}
+
+pub fn treasury<S: Storage>(storage: &mut S) -> Singleton<'_, S, Treasury> {
+    singleton(storage, TREASURY)
+}
+
+pub fn treasury_read<S: Storage>(storage: &S) -> ReadonlySingleton<'_, S, Treasury> {
+    singleton_read(storage, TREASURY)
+}
+
+/// The sponsors log, keyed by the big-endian index of each donation
+pub fn sponsorships<S: Storage>(storage: &mut S) -> Bucket<'_, S, Sponsorship> {
+    bucket(SPONSORSHIPS, storage)
+}
+
+pub fn sponsorships_read<S: Storage>(storage: &S) -> ReadonlyBucket<'_, S, Sponsorship> {
+    bucket_read(SPONSORSHIPS, storage)
+}
+
+/// A page of a list query, over a list stored under consecutive indexes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pagination {
+    /// The index of the last entry of the previous page
+    pub start_after: Option<u32>,
+    /// The amount of entries to return. Defaults to `DEFAULT_LIMIT`, and can be at most
+    /// `MAX_LIMIT`.
+    pub limit: Option<u32>,
+}
+
+impl Pagination {
+    pub fn new(start_after: Option<u32>, limit: Option<u32>) -> Self {
+        Self { start_after, limit }
+    }
+
+    /// The indexes on this page, in a list of `size` entries
+    pub fn range(&self, size: u32) -> Range<u32> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let start = self.start_after.map_or(0, |index| index.saturating_add(1));
+        let end = start.saturating_add(limit).min(size);
+        start..end.max(start)
+    }
+}
+
+/// Load a page of a list of `size` entries, stored in a bucket under big-endian indexes
+pub fn paginate_bucket<S: Storage, T: Serialize + DeserializeOwned>(
+    bucket: &ReadonlyBucket<'_, S, T>,
+    size: u32,
+    page: Pagination,
+) -> StdResult<Vec<T>> {
+    page.range(size)
+        .map(|index| bucket.load(&index.to_be_bytes()))
+        .collect()
+}
+
+/// Storage that reads through to the contract's storage, but keeps all writes to itself,
+/// so handlers can run on it without changing any state
+pub struct ScratchStorage<'a, S: ReadonlyStorage> {
+    base: &'a S,
+    /// The values written so far, or `None` for removed keys, in key order so batches
+    /// are always applied the same way
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a, S: ReadonlyStorage> ScratchStorage<'a, S> {
+    pub fn new(base: &'a S) -> Self {
+        Self {
+            base,
+            writes: BTreeMap::new(),
+        }
+    }
+}
+
+impl<'a, S: ReadonlyStorage> ReadonlyStorage for ScratchStorage<'a, S> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.base.get(key),
+        }
+    }
+}
+
+impl<'a, S: ReadonlyStorage> Storage for ScratchStorage<'a, S> {
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+/// Run `f` on scratch storage over `storage`, and only apply the writes it made once it
+/// succeeds. If `f` fails, even after some of its writes, `storage` is left untouched.
+pub fn write_batch<S, T, F>(storage: &mut S, f: F) -> StdResult<T>
+where
+    S: Storage,
+    F: FnOnce(&mut ScratchStorage<S>) -> StdResult<T>,
+{
+    let mut scratch = ScratchStorage::new(storage);
+    let result = f(&mut scratch)?;
+
+    for (key, value) in scratch.writes {
+        match value {
+            Some(value) => storage.set(&key, &value),
+            None => storage.remove(&key),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_storage::to_length_prefixed;
+
+    use cosmwasm_std::StdError;
+
+    use super::*;
+    use crate::bounded::{Password, Username};
+    use crate::rules::RULES_VERSION;
+    use crate::state::{Coords, Game};
+
+    #[test]
+    fn test_namespaces_are_distinct() {
+        let namespaces = [
+            CONFIG,
+            GAMES,
+            GAME_LIST,
+            GAME_LIST_SIZE,
+            GAME_LIST_INDEX,
+            TEMPLATES,
+            TEMPLATE_LIST,
+            TEMPLATE_LIST_SIZE,
+            TEMPLATE_LIST_INDEX,
+            ARCHIVE,
+            ARCHIVE_SIZE,
+            ARCHIVE_INDEX,
+            HEAD_TO_HEAD,
+            TREASURY,
+            SPONSORSHIPS,
+        ];
+        for (index, namespace) in namespaces.iter().enumerate() {
+            assert!(!namespaces[..index].contains(namespace));
+        }
+    }
+
+    #[test]
+    fn test_pagination() {
+        assert_eq!(Pagination::default().range(50), 0..DEFAULT_LIMIT);
+        assert_eq!(Pagination::new(Some(4), Some(3)).range(50), 5..8);
+        assert_eq!(
+            Pagination::new(Some(4), Some(100)).range(50),
+            5..5 + MAX_LIMIT
+        );
+        assert_eq!(Pagination::new(Some(8), None).range(5), 9..9);
+        assert_eq!(
+            Pagination::new(Some(u32::MAX), None).range(5),
+            u32::MAX..u32::MAX
+        );
+    }
+
+    #[test]
+    fn test_games_are_kept_apart() {
+        let mut storage = MockStorage::new();
+        let state = GameState::default();
+        save_game(&mut storage, "pasture", &state).unwrap();
+
+        assert_eq!(load_game(&storage, "pasture").unwrap(), Some(state));
+        assert_eq!(load_game(&storage, "pasture party").unwrap(), None);
+
+        remove_game(&mut storage, "pasture");
+        assert_eq!(load_game(&storage, "pasture").unwrap(), None);
+    }
+
+    #[test]
+    fn test_write_batch() {
+        let mut storage = MockStorage::new();
+        storage.set(b"herd", b"sheep");
+
+        let res = write_batch(&mut storage, |batch| {
+            batch.set(b"pen", b"lamb");
+            batch.remove(b"herd");
+            assert_eq!(batch.get(b"herd"), None);
+            Err::<(), _>(StdError::generic_err("wolf"))
+        });
+        res.unwrap_err();
+        assert_eq!(storage.get(b"herd"), Some(b"sheep".to_vec()));
+        assert_eq!(storage.get(b"pen"), None);
+
+        let res = write_batch(&mut storage, |batch| {
+            batch.set(b"pen", b"lamb");
+            batch.remove(b"herd");
+            Ok(batch.get(b"pen"))
+        });
+        assert_eq!(res.unwrap(), Some(b"lamb".to_vec()));
+        assert_eq!(storage.get(b"herd"), None);
+        assert_eq!(storage.get(b"pen"), Some(b"lamb".to_vec()));
+    }
+
+    /// A game stored before state versions, with shots kept on the shooter's pasture.
+    /// This is the exact layout the first release of the contract stored games in.
+    const GAME_V0: &[u8] = concat!(
+        r#"{"players":[{"username":"alice","password":"hunter2","pasture":{"#,
+        r#""herds":[{"coords":{"x":0,"y":0},"length":2,"orientation":"horizontal"}],"#,
+        r#""shots":[{"x":5,"y":5}]}},{"username":"bob","password":"hunter3","pasture":{"#,
+        r#""herds":[{"coords":{"x":0,"y":0},"length":2,"orientation":"vertical"}],"#,
+        r#""shots":[]}}],"turn":1,"next_shot":null}"#
+    )
+    .as_bytes();
+
+    /// A game stored in the last layout of state version 1, before the optional rules
+    /// were grouped, exactly as that release of the contract stored it
+    const GAME_V1: &[u8] = concat!(
+        r#"{"players":[{"username":"alice","password":"hunter2","pasture":{"#,
+        r#""herds":[{"coords":{"x":0,"y":0},"length":2,"orientation":"horizontal"}],"#,
+        r#""shots":[]},"address":"secret1alice","last_action":12340},"#,
+        r#"{"username":"bob","password":"hunter3","pasture":{"#,
+        r#""herds":[{"coords":{"x":0,"y":0},"length":2,"orientation":"vertical"}],"#,
+        r#""shots":[{"x":5,"y":5}]},"address":"secret1bob","last_action":null}],"#,
+        r#""board":{"size":6,"fleet":[{"length":2,"count":1}],"shoot_again_on_hit":true},"#,
+        r#""turn":1,"next_shot":null,"opening":null,"turns":1,"callback":null,"#,
+        r#""expires_at":12400,"turn_clock":5,"turn_started_at":12340,"#,
+        r#""takeback_requested":false,"winner":null,"deposit":null,"practice":false,"#,
+        r#""version":1}"#
+    )
+    .as_bytes();
+
+    #[test]
+    fn test_legacy_games_are_migrated() {
+        // The fixtures, with the turn clock, shoot-again rule, turn count and pasture size
+        // they were stored with
+        let fixtures = [
+            (GAME_V0, None, false, 0, 10),
+            (GAME_V1, Some(5), true, 1, 6),
+        ];
+        for (fixture, turn_clock, shoot_again_on_hit, turns, size) in &fixtures {
+            let mut storage = MockStorage::new();
+            prefixed(GAMES, &mut storage).set(&to_length_prefixed(b"pasture"), fixture);
+            let game = Game::load(&storage, "pasture".to_string()).unwrap();
+
+            let rules = game.rules();
+            assert_eq!(rules.version, RULES_VERSION);
+            assert_eq!(rules.turn_clock, *turn_clock);
+            assert_eq!(rules.shoot_again_on_hit, *shoot_again_on_hit);
+            assert!(!rules.opening_salvo);
+            assert_eq!(game.turns(), *turns);
+            assert_eq!(game.board().size, *size);
+
+            // The shot alice fired is on bob's pasture
+            let shot = Coords::new(5, 5);
+            let players = game.players();
+            assert_eq!(players[0].pasture().shot_at(shot), None);
+            assert!(players[1].pasture().shot_at(shot).is_some());
+
+            // Saving the game stores it in the current layout
+            let state = load_game(&storage, "pasture").unwrap().unwrap();
+            game.save(&mut storage).unwrap();
+            assert_ne!(load_game(&storage, "pasture").unwrap(), Some(state));
+            let reloaded = Game::load(&storage, "pasture".to_string()).unwrap();
+            assert_eq!(reloaded.rules(), rules);
+        }
+    }
+
+    #[test]
+    fn test_legacy_games_with_long_credentials_load() {
+        let username = "a".repeat(Username::MAX_LEN + 1);
+        let fixture = String::from_utf8_lossy(GAME_V1)
+            .replace("\"alice\"", &format!("{:?}", username))
+            .replace("hunter2", &"2".repeat(Password::MAX_LEN + 1));
+        let mut storage = MockStorage::new();
+        prefixed(GAMES, &mut storage).set(&to_length_prefixed(b"pasture"), fixture.as_bytes());
+
+        let game = Game::load(&storage, "pasture".to_string()).unwrap();
+        assert_eq!(game.players()[0].username(), username);
+        game.save(&mut storage).unwrap();
+        let reloaded = Game::load(&storage, "pasture".to_string()).unwrap();
+        assert_eq!(reloaded.players()[0].username(), username);
+    }
+}
@@ -0,0 +1,88 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Coin, HumanAddr, StdResult, Storage};
+
+use crate::storage::{self, paginate_bucket, Pagination};
+
+/// Funds donated to the contract, for prize pools.
+///
+/// This is bookkeeping separate from anything players put at stake, so donations can never
+/// be paid out as part of a game, and player funds can never be counted as donations.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Treasury {
+    /// The total amount donated, per denomination
+    pub balance: Vec<Coin>,
+    /// The total amount of lobby deposits forfeited to the treasury, per denomination
+    #[serde(default)]
+    pub forfeited: Vec<Coin>,
+    /// The number of donations made so far
+    pub sponsorships: u32,
+}
+
+impl Treasury {
+    pub fn load<S: Storage>(storage: &S) -> StdResult<Self> {
+        storage::treasury_read(storage)
+            .may_load()
+            .map(Option::unwrap_or_default)
+    }
+
+    pub fn save<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
+        storage::treasury(storage).save(self)
+    }
+
+    /// Record a donation, both in the balance and in the sponsors log
+    pub fn donate<S: Storage>(
+        &mut self,
+        storage: &mut S,
+        sponsorship: Sponsorship,
+    ) -> StdResult<()> {
+        for coin in sponsorship.amount.iter() {
+            add_coin(&mut self.balance, coin);
+        }
+        storage::sponsorships(storage).save(&self.sponsorships.to_be_bytes(), &sponsorship)?;
+        self.sponsorships += 1;
+
+        Ok(())
+    }
+
+    /// Record forfeited funds. These aren't donations, so they are kept out of the balance
+    /// and the sponsors log.
+    pub fn collect(&mut self, coin: &Coin) {
+        add_coin(&mut self.forfeited, coin);
+    }
+
+    /// Load a page of the sponsors log
+    pub fn sponsorships<S: Storage>(
+        &self,
+        storage: &S,
+        page: Pagination,
+    ) -> StdResult<Vec<Sponsorship>> {
+        paginate_bucket(
+            &storage::sponsorships_read(storage),
+            self.sponsorships,
+            page,
+        )
+    }
+}
+
+/// A single donation to the treasury
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Sponsorship {
+    pub sponsor: HumanAddr,
+    pub amount: Vec<Coin>,
+    /// The block height the donation was made at
+    pub height: u64,
+}
+
+/// Add a coin to a balance that holds one entry per denomination
+pub fn add_coin(balance: &mut Vec<Coin>, coin: &Coin) {
+    if let Some(existing) = balance
+        .iter_mut()
+        .find(|existing| existing.denom == coin.denom)
+    {
+        existing.amount += coin.amount;
+    } else {
+        balance.push(coin.clone());
+    }
+}